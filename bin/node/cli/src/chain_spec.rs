@@ -22,7 +22,7 @@ use serde::{Serialize, Deserialize};
 use node_runtime::{
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig, CouncilConfig, DemocracyConfig,
 	GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig, SudoConfig,
-	SystemConfig, TechnicalCommitteeConfig, DidConfig, AdsConfig, WASM_BINARY,
+	SystemConfig, TechnicalCommitteeConfig, DidConfig, AdsConfig, OracleConfig, WASM_BINARY,
 };
 use node_runtime::Block;
 use node_runtime::constants::currency::*;
@@ -313,6 +313,10 @@ pub fn testnet_genesis(
 			contract: hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into(),
 			min_deposit: 500 * DOLLARS,
 		}),
+		oracle: Some(OracleConfig {
+			authorities: vec![],
+			threshold: 2,
+		}),
 	}
 }
 
@@ -516,6 +520,10 @@ fn prochain_testnet_genesis() -> GenesisConfig {
 				contract: hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into(),
 				min_deposit: 500 * DOLLARS,
 			}),
+			oracle: Some(OracleConfig {
+				authorities: vec![],
+				threshold: 2,
+			}),
 		}
 	}
 
@@ -0,0 +1,53 @@
+//! Runtime API definition for the ads module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// An active campaign as listed to ad-serving backends.
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CampaignInfo<Hash, Balance> {
+	/// Id of the campaign.
+	pub campaign_id: Hash,
+	/// What the campaign pays per click.
+	pub single_click_fee: Balance,
+	/// Budget the campaign has left.
+	pub surplus: Balance,
+	/// Tags the campaign was published with.
+	pub tags: Vec<Vec<u8>>,
+}
+
+/// Aggregates of a campaign for advertiser dashboards.
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CampaignReport<Balance, Moment> {
+	/// Budget the campaign has left.
+	pub surplus: Balance,
+	/// Paid out to users, publishers and superiors so far.
+	pub paid: Balance,
+	/// Receipts settled so far.
+	pub impressions: u64,
+	/// User dids paid at least once.
+	pub unique_users: u32,
+	/// Spend per day start for the last days, oldest first.
+	pub daily_spend: Vec<(Moment, Balance)>,
+}
+
+sp_api::decl_runtime_apis! {
+	pub trait AdsApi<Hash, Balance, Moment> where
+		Hash: Codec,
+		Balance: Codec,
+		Moment: Codec,
+	{
+		/// Active campaigns carrying any of `tags`, best paying and then richest first.
+		fn active_campaigns(tags: Vec<Vec<u8>>) -> Vec<CampaignInfo<Hash, Balance>>;
+		/// Totals, remaining budget and daily spend of a campaign, if it exists.
+		fn campaign_report(campaign_id: Hash) -> Option<CampaignReport<Balance, Moment>>;
+	}
+}
@@ -0,0 +1,99 @@
+//! RPC interface for the ads module.
+
+use std::sync::Arc;
+use codec::Codec;
+use sp_blockchain::HeaderBackend;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, ProvideRuntimeApi}};
+use sp_core::Bytes;
+pub use ads_rpc_runtime_api::{AdsApi as AdsRuntimeApi, CampaignInfo, CampaignReport};
+pub use self::gen_client::Client as AdsClient;
+
+/// Ads RPC methods.
+#[rpc]
+pub trait AdsApi<BlockHash, Hash, Balance, Moment> {
+	/// Active campaigns carrying any of `tags`, sorted by single click fee and then remaining budget.
+	#[rpc(name = "ads_activeCampaigns")]
+	fn active_campaigns(&self, tags: Vec<Bytes>, at: Option<BlockHash>) -> Result<Vec<CampaignInfo<Hash, Balance>>>;
+
+	/// Totals, remaining budget and daily spend of a campaign.
+	#[rpc(name = "ads_campaignReport")]
+	fn campaign_report(&self, campaign_id: Hash, at: Option<BlockHash>) -> Result<Option<CampaignReport<Balance, Moment>>>;
+}
+
+/// A struct that implements the [`AdsApi`].
+pub struct Ads<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Ads<C, B> {
+	/// Create new `Ads` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Ads { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, Hash, Balance, Moment> AdsApi<<Block as BlockT>::Hash, Hash, Balance, Moment> for Ads<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi,
+	C: HeaderBackend<Block>,
+	C::Api: AdsRuntimeApi<Block, Hash, Balance, Moment>,
+	Hash: Codec,
+	Balance: Codec,
+	Moment: Codec,
+{
+	fn active_campaigns(
+		&self,
+		tags: Vec<Bytes>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<CampaignInfo<Hash, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let tags = tags.into_iter().map(|tag| tag.0).collect();
+		api.active_campaigns(&at, tags).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query active campaigns.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn campaign_report(
+		&self,
+		campaign_id: Hash,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<CampaignReport<Balance, Moment>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.campaign_report(&at, campaign_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query campaign report.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -5,18 +5,197 @@ mod tests;
 use codec::{Decode, Encode};
 use rstd::vec::Vec;
 use support::{
-	decl_event, decl_module, decl_storage, ensure,
+	decl_error, decl_event, decl_module, decl_storage, ensure, print, Parameter,
+	traits::{Currency, ReservableCurrency, ExistenceRequirement, WithdrawReason, OnUnbalanced, Imbalance, Get},
+	dispatch::DispatchResult,
+	storage::unhashed,
+};
+use runtime_io::hashing::blake2_256;
+use sp_runtime::{
+	Perbill, ModuleId,
+	traits::{Zero, One, CheckedSub, CheckedAdd, Hash, Verify, IdentifyAccount, EnsureOrigin, Saturating, AccountIdConversion},
 };
-use sp_runtime::traits::{Zero, CheckedSub, CheckedAdd};
 use system::ensure_signed;
+use did::DidOrName;
+use ads_rpc_runtime_api::{CampaignInfo, CampaignReport};
+
+/// Derives the keyless account holding every campaign budget.
+const MODULE_ID: ModuleId = ModuleId(*b"pra/ads_");
+
+/// Tags the bytes users sign on a receipt, with the genesis hash it keeps them from verifying on other chains.
+const RECEIPT_DOMAIN: &[u8] = b"prochain/ads/receipt";
+
+// dids `on_initialize` looks for a legacy campaign at per block while migrating
+const MAX_LEGACY_DIDS: u64 = 64;
+
+// expiry buckets `on_initialize` walks at most per block, a stalled chain catches up over several blocks
+const MAX_EXPIRY_BUCKETS: u32 = 64;
+
+/// Storage layout of the module, campaigns were keyed by advertiser did and funded
+/// on the `Contract` account before version 1.
+const STORAGE_VERSION: u32 = 1;
+
+type NegativeImbalanceOf<T> = <balances::Module<T> as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
 
 pub trait Trait: balances::Trait + timestamp::Trait + did::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// How many expired campaigns `on_initialize` closes at most per block.
+	type MaxExpiriesPerBlock: Get<u32>;
+	/// Paid out of the surplus to whoever closes an expired campaign with `close_expired`.
+	type CloseReward: Get<Self::Balance>;
+	/// Signature users put on their click receipts.
+	type Signature: Parameter + Verify<Signer = Self::Public>;
+	/// Key behind `Signature`, identifying the account of the user did.
+	type Public: IdentifyAccount<AccountId = Self::AccountId>;
+	/// How many receipts one `settle` call may carry.
+	type MaxReceiptsPerSettle: Get<u32>;
+	/// How long users can claim from a posted payout root.
+	type ClaimPeriod: Get<Self::Moment>;
+	/// How many tags a campaign can carry.
+	type MaxTags: Get<u32>;
+	/// How long a single tag can be.
+	type MaxTagLength: Get<u32>;
+	/// Length of the day `daily_budget` applies to. Must not be zero.
+	type DayLength: Get<Self::Moment>;
+	/// How many days of spend history a campaign keeps.
+	type StatsHistoryDays: Get<u32>;
+	/// Origin allowed to change the platform fee.
+	type PlatformFeeOrigin: EnsureOrigin<Self::Origin>;
+	/// Where the platform fee on ad spend goes.
+	type PlatformFeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	/// Origin reviewing campaigns.
+	type ModerationOrigin: EnsureOrigin<Self::Origin>;
+	/// Whether suspending a campaign refunds its surplus instead of freezing it.
+	type RefundOnSuspend: Get<bool>;
+	/// Reserved from the reporter of a fraud report, forfeited if the report is dismissed.
+	type ReportBond: Get<Self::Balance>;
+	/// Share of a confirmed slash paid to the reporter.
+	type ReporterReward: Get<Perbill>;
+	/// Origin adjudicating fraud reports.
+	type ReportOrigin: EnsureOrigin<Self::Origin>;
+	/// Where slashed funds and forfeited bonds go, apart from the reporter reward.
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+}
+
+/// How fast a campaign may spend, zero meaning no limit.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct Pacing<Balance, Moment> {
+	/// Rewards one user did can get per `cap_period`
+	max_rewards_per_user: u32,
+	cap_period: Moment,
+	/// Spend allowed per day
+	daily_budget: Balance,
+}
+
+/// Spend of a campaign, reset lazily when a new day starts.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct CampaignStats<Balance, Moment> {
+	/// Start of the current day
+	day_start: Moment,
+	/// Paid out or locked for claims since `day_start`
+	spent_today: Balance,
+}
+
+/// Running totals of a campaign.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct CampaignTotals<Balance> {
+	/// Paid to users, publishers and superiors, platform fees left out
+	paid: Balance,
+	/// Settled receipts
+	impressions: u64,
+	/// User dids paid at least once
+	unique_users: u32,
+}
+
+/// Rewards a user did got from a campaign, reset lazily after `cap_period`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct RewardCounter<Moment> {
+	period_start: Moment,
+	count: u32,
+}
+
+/// Payouts of one campaign epoch, locked out of the surplus until claimed or reclaimed.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct EpochPayout<Hash, Balance, Moment> {
+	/// Merkle root over `(user did, amount)` leaves
+	root: Hash,
+	total: Balance,
+	claimed: Balance,
+	deadline: Moment,
+}
+
+/// How a click fee is split, the user getting what is left.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct RevenueShare {
+	/// Paid to the did of the app showing the ad
+	publisher: Perbill,
+	/// Paid to the superior of the user did, if it has an account
+	superior: Perbill,
+}
+
+/// A click or view of a campaign, the account of `user` signs its `Module::receipt_payload`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Receipt<Hash, Moment> {
+	pub campaign: Hash,
+	pub user: Hash,
+	/// Did or name of the app the ad was shown in
+	pub publisher: Option<DidOrName<Hash>>,
+	/// Strictly increasing per campaign and user
+	pub nonce: u64,
+	pub time: Moment,
+}
+
+/// What a fraud report accuses.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum ReportTarget<Hash> {
+	/// The aggregator settled fake receipts for the campaign
+	Payout(Hash),
+	/// The user did farmed rewards, a name is stored as the did it resolved to
+	User(DidOrName<Hash>),
+}
+
+/// A bonded fraud report waiting for `ReportOrigin`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub struct FraudReport<AccountId, Hash, Balance> {
+	reporter: AccountId,
+	target: ReportTarget<Hash>,
+	/// Hash of the evidence kept off chain
+	evidence: Hash,
+	bond: Balance,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub enum CampaignStatus {
+	/// Waiting for `ModerationOrigin` to review it
+	Pending,
+	/// Can pay out until `create_time + period`
+	Approved,
+	/// Turned down in review, the surplus has been refunded
+	Rejected,
+	/// Taken down after approval, frozen or refunded according to `RefundOnSuspend`
+	Suspended,
+	/// Past its window, the surplus has been or is being refunded
+	Expired,
+}
+
+impl Default for CampaignStatus {
+	fn default() -> Self {
+		CampaignStatus::Pending
+	}
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
-pub struct AdsMetadata<Balance, Moment> {
+pub struct AdsMetadata<Hash, Balance, Moment> {
+	owner: Hash,
 	advertiser: Vec<u8>,
   topic: Vec<u8>,
   total_amount: Balance,
@@ -25,50 +204,316 @@ pub struct AdsMetadata<Balance, Moment> {
   single_click_fee: Balance,
   create_time: Moment,
   period: Moment,
+  status: CampaignStatus,
+  tags: Vec<Vec<u8>>,
+  pacing: Pacing<Balance, Moment>,
+  revenue_share: RevenueShare,
+}
+
+// a campaign of storage version 0, one per advertiser did
+#[derive(Encode, Decode)]
+struct LegacyAdsMetadata<Balance, Moment> {
+	advertiser: Vec<u8>,
+	topic: Vec<u8>,
+	total_amount: Balance,
+	surplus: Balance,
+	gas_fee_used: Balance,
+	single_click_fee: Balance,
+	create_time: Moment,
+	period: Moment,
+}
+
+// what one user did gets out of a settlement
+struct UserPayout<Hash, Balance, Moment> {
+	user: Hash,
+	nonce: u64,
+	amount: Balance,
+	superior: Option<Hash>,
+	superior_amount: Balance,
+	rewards: RewardCounter<Moment>,
+	receipts: u64,
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as AdsModule {
+			/// Aggregator allowed to settle receipts and post payout roots.
 			Contract get(contract) config(): T::AccountId;
 			MinDeposit get(min_deposit) config(): T::Balance;
 
-			AdsRecords get(ads_records): map T::Hash => AdsMetadata<T::Balance, T::Moment>;
+			/// Campaigns by campaign id.
+			AdsRecords get(ads_records): linked_map T::Hash => AdsMetadata<T::Hash, T::Balance, T::Moment>;
+			/// Campaign ids published by an advertiser did.
+			CampaignsOf get(campaigns_of): map T::Hash => Vec<T::Hash>;
+			/// Spend of a campaign for the current day.
+			Stats get(stats): map T::Hash => CampaignStats<T::Balance, T::Moment>;
+			/// Spend of a campaign per day start for the last `StatsHistoryDays` days up to the last payout,
+			/// oldest first. Days without spend have no entry.
+			SpendHistory get(spend_history): map T::Hash => Vec<(T::Moment, T::Balance)>;
+			/// Running totals of a campaign.
+			Totals get(totals): map T::Hash => CampaignTotals<T::Balance>;
+			/// Whether a user did was ever paid by a campaign.
+			Rewarded get(rewarded): map (T::Hash, T::Hash) => bool;
+			/// Rewards per campaign and user did for the current cap period.
+			UserRewards get(user_rewards): map (T::Hash, T::Hash) => RewardCounter<T::Moment>;
+			/// Active campaign ids by tag.
+			CampaignsByTag get(campaigns_by_tag): map Vec<u8> => Vec<T::Hash>;
+			/// Pending and approved campaigns by the expiry bucket their end time falls in.
+			ExpiryQueue get(expiry_queue): map T::Moment => Vec<T::Hash>;
+			/// Expiry bucket a campaign is queued in.
+			ExpiryOf get(expiry_of): map T::Hash => Option<T::Moment>;
+			/// First expiry bucket not drained yet, set once the first campaign is queued.
+			NextExpiry get(next_expiry): Option<T::Moment>;
+			/// Last settled receipt nonce per campaign and user did.
+			ReceiptNonce get(receipt_nonce): map (T::Hash, T::Hash) => u64;
+			/// Posted payout roots by campaign id and epoch.
+			EpochPayouts get(epoch_payouts): linked_map (T::Hash, u32) => EpochPayout<T::Hash, T::Balance, T::Moment>;
+			/// Latest epoch posted for a campaign.
+			LastEpoch get(last_epoch): map T::Hash => u32;
+			/// Whether a user did claimed from a campaign epoch.
+			Claimed get(claimed): map (T::Hash, u32, T::Hash) => bool;
 			AllAdsCount get(all_ads_count): u64;
+			/// Storage layout the module is at, see `STORAGE_VERSION`.
+			StorageVersion get(storage_version) build(|_config: &GenesisConfig<T>| STORAGE_VERSION): u32;
+			/// Next did index the migration to `STORAGE_VERSION` looks for a legacy campaign at.
+			MigrationCursor get(migration_cursor): u64;
+			/// Surplus of a migrated legacy campaign the contract account could not move into the escrow.
+			UnfundedSurplus get(unfunded_surplus): map T::Hash => T::Balance;
+			/// Cut of every payout charged to the campaign on top of it.
+			PlatformFee get(platform_fee): Perbill;
+
+			/// Funds the aggregator reserved to back its settlements.
+			AggregatorBond get(aggregator_bond): T::Balance;
+			/// Open fraud reports by report id.
+			Reports get(reports): map u64 => Option<FraudReport<T::AccountId, T::Hash, T::Balance>>;
+			NextReportId get(next_report_id): u64;
+			/// Open reports against the aggregator, which can't unbond while there are any.
+			OpenPayoutReports get(open_payout_reports): u32;
     }
+	add_extra_genesis {
+		// the escrow is kept alive so payouts never reap it and burn its dust
+		build(|_config: &GenesisConfig<T>| {
+			let _ = <balances::Module<T> as Currency<_>>::deposit_creating(
+				&<Module<T>>::escrow_account(),
+				<balances::Module<T> as Currency<_>>::minimum_balance(),
+			);
+		});
+	}
 }
 
 decl_event! {
   pub enum Event<T>
   where
+    <T as system::Trait>::AccountId,
     <T as system::Trait>::Hash,
     <T as balances::Trait>::Balance,
 		<T as timestamp::Trait>::Moment,
     {
+      /// advertiser did, campaign id, total amount
       Published(Hash, Hash, Balance),
-			Deposited(Hash, Hash, Balance),
+			/// campaign id, value
+			Deposited(Hash, Balance),
+			/// campaign id, value
 			Withdrawl(Hash, Balance),
+			/// campaign id, user did, value
 			Distributed(Hash, Hash, Balance),
+			/// campaign id, single click fee, period
 			AdsUpdated(Hash, Balance, Moment),
+			/// campaign id, new tags
+			TagsUpdated(Hash, Vec<Vec<u8>>),
+			/// campaign id, max rewards per user, cap period, daily budget
+			PacingUpdated(Hash, u32, Moment, Balance),
+			/// campaign id, publisher share, superior share
+			RevenueShareSet(Hash, Perbill, Perbill),
+			/// campaign id, publisher did, value
+			PublisherPaid(Hash, Hash, Balance),
+			/// campaign id, superior did, value
+			SuperiorPaid(Hash, Hash, Balance),
+			/// new platform fee
+			PlatformFeeSet(Perbill),
+			/// campaign id, platform fee charged
+			PlatformFeeCharged(Hash, Balance),
+			/// campaign id
+			Expired(Hash),
+			/// campaign id
+			Approved(Hash),
+			/// campaign id
+			Rejected(Hash),
+			/// campaign id
+			Suspended(Hash),
+			/// campaign id, advertiser did, refunded surplus
+			Refunded(Hash, Hash, Balance),
+			/// campaign id, epoch, root, locked total
+			RootPosted(Hash, u32, Hash, Balance),
+			/// campaign id, epoch, user did, value
+			Claimed(Hash, u32, Hash, Balance),
+			/// campaign id, epoch, unclaimed value
+			Reclaimed(Hash, u32, Balance),
+			/// value added to the aggregator bond
+			AggregatorBonded(Balance),
+			/// value taken from the aggregator bond
+			AggregatorUnbonded(Balance),
+			/// report id, reporter, target
+			FraudReported(u64, AccountId, ReportTarget<Hash>),
+			/// report id, slashed value, reporter reward
+			FraudConfirmed(u64, Balance, Balance),
+			/// report id, forfeited reporter bond
+			ReportDismissed(u64, Balance),
+			/// campaign id, legacy surplus the escrow is still owed
+			LegacySurplusUnfunded(Hash, Balance),
+			/// campaign id, legacy surplus moved into the escrow
+			LegacySurplusFunded(Hash, Balance),
     }
 }
 
+decl_error! {
+	/// Error for the ads module.
+	pub enum Error for Module<T: Trait> {
+		/// The sender has no did yet
+		NoDid,
+		/// The user to be paid has no did yet
+		UserNoDid,
+		/// The publisher of a receipt has no did yet
+		PublisherNoDid,
+		/// The revenue shares add up to more than the whole fee
+		InvalidShare,
+		/// The amount is below the minimum deposit
+		BelowMinDeposit,
+		/// The campaign does not exist
+		NotPublished,
+		/// The campaign belongs to another did
+		NotOwner,
+		/// The ads surplus does not cover the amount
+		InsufficientSurplus,
+		/// The escrow account does not hold the amount on top of its existential deposit
+		InsufficientEscrow,
+		/// Only the contract account can settle receipts
+		NotContract,
+		/// No receipts were given
+		NoReceipts,
+		/// More receipts than `MaxReceiptsPerSettle`
+		TooManyReceipts,
+		/// The receipt is for another campaign
+		WrongCampaign,
+		/// The receipt time is outside the campaign window
+		InvalidReceiptTime,
+		/// The receipt is not signed by the user did
+		BadSignature,
+		/// The receipt nonce has been settled already
+		ReplayedReceipt,
+		/// The epoch is not after the last posted one
+		StaleEpoch,
+		/// No root is posted for the epoch
+		NoEpoch,
+		/// The user did claimed from the epoch already
+		AlreadyClaimed,
+		/// The proof does not lead to the posted root
+		InvalidProof,
+		/// The claim deadline has passed
+		ClaimExpired,
+		/// The claim deadline has not passed yet
+		ClaimRunning,
+		/// More tags than `MaxTags`
+		TooManyTags,
+		/// A tag is empty, longer than `MaxTagLength` or given twice
+		InvalidTag,
+		/// The user did got its maximum rewards for this period
+		FrequencyCapReached,
+		/// The campaign spent its daily budget
+		DailyBudgetExceeded,
+		/// The campaign window is over
+		CampaignExpired,
+		/// The campaign window is not over yet
+		CampaignRunning,
+		/// There is no surplus left to refund
+		NothingToRefund,
+		/// The campaign has not been approved
+		NotApproved,
+		/// The campaign is suspended and its funds frozen
+		CampaignFrozen,
+		/// The campaign status does not allow this
+		InvalidStatus,
+		/// The fraud report does not exist
+		NoReport,
+		/// Fraud reports against the aggregator are still open
+		ReportsOpen,
+		/// The aggregator bond does not cover the amount
+		InsufficientBond,
+		/// An arithmetic overflow happened
+		Overflow,
+		/// The campaign is owed no legacy surplus
+		NoUnfundedSurplus,
+	}
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
 		fn deposit_event() = default;
-    
-    fn publish(origin, name: Vec<u8>, topic: Vec<u8>, total_amount: T::Balance, single_click_fee: T::Balance, period: T::Moment) {
+
+		const MaxExpiriesPerBlock: u32 = T::MaxExpiriesPerBlock::get();
+		const CloseReward: T::Balance = T::CloseReward::get();
+
+		fn on_initialize(_n: T::BlockNumber) {
+			if Self::storage_version() < STORAGE_VERSION {
+				Self::migrate_legacy_campaigns();
+			}
+
+			let mut bucket = match Self::next_expiry() {
+				Some(bucket) => bucket,
+				None => return,
+			};
+			let now = <timestamp::Module<T>>::get();
+			let granularity = Self::expiry_granularity();
+			let mut closes = T::MaxExpiriesPerBlock::get() as usize;
+
+			// every campaign in a bucket up to `now` has passed its end
+			for _ in 0..MAX_EXPIRY_BUCKETS {
+				if bucket > now || closes == 0 {
+					break;
+				}
+				let mut due = Self::expiry_queue(bucket);
+				let rest = due.split_off(closes.min(due.len()));
+				closes -= due.len();
+
+				for campaign_id in due {
+					<ExpiryOf<T>>::remove(campaign_id);
+					if Self::close(campaign_id, None).is_err() {
+						// left as it was, `close_expired` can retry once the refund is payable
+						print("failed to refund expired campaign");
+					}
+				}
+
+				if !rest.is_empty() {
+					<ExpiryQueue<T>>::insert(bucket, rest);
+					break;
+				}
+				<ExpiryQueue<T>>::remove(bucket);
+				bucket = bucket.saturating_add(granularity);
+			}
+			<NextExpiry<T>>::put(bucket);
+		}
+
+    fn publish(origin, name: Vec<u8>, topic: Vec<u8>, tags: Vec<Vec<u8>>, total_amount: T::Balance, single_click_fee: T::Balance, period: T::Moment) {
       let sender = ensure_signed(origin)?;
 
-      ensure!(<did::Identity<T>>::exists(sender.clone()), "did does not exists");
-			ensure!(total_amount >= Self::min_deposit(), "min deposit 500 pra");
+      ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+			ensure!(total_amount >= Self::min_deposit(), Error::<T>::BelowMinDeposit);
+			Self::validate_tags(&tags)?;
 
-      let from_did = <did::Module<T>>::identity(sender);
+      let from_did = <did::Module<T>>::identity(&sender);
       let create_time = <timestamp::Module<T>>::get();
 
-			let contract = <did::Module<T>>::identity(Self::contract());
-			<did::Module<T>>::transfer_by_did(from_did, contract, total_amount, "开户广告费".as_bytes().to_vec())?;
+			// every campaign gets its own id, so one did can run several at once
+			let all_ads_count = Self::all_ads_count();
+			let new_count = all_ads_count.checked_add(1)
+					.ok_or(Error::<T>::Overflow)?;
+			let campaign_id = T::Hashing::hash_of(&(from_did, all_ads_count));
+
+			Self::pay_in(&sender, total_amount)?;
 
       let ads_metadata = AdsMetadata {
+        owner: from_did,
         advertiser: name,
         topic,
         total_amount,
@@ -76,107 +521,1051 @@ decl_module! {
         gas_fee_used: Zero::zero(),
         single_click_fee,
         create_time,
-        period
+        period,
+        status: CampaignStatus::Pending,
+        tags: tags.clone(),
+        pacing: Pacing::default(),
+        revenue_share: RevenueShare::default(),
       };
+      let end = create_time.checked_add(&period).ok_or(Error::<T>::Overflow)?;
 
-			<AdsRecords<T>>::insert(from_did, ads_metadata);
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+			<CampaignsOf<T>>::mutate(from_did, |campaigns| campaigns.push(campaign_id));
+			Self::schedule_expiry(end, campaign_id);
 
 			// update count
-			let all_ads_count = Self::all_ads_count();
-			let new_count = all_ads_count.checked_add(1)
-					.ok_or("Overflow adding new ads")?;
 			<AllAdsCount>::put(new_count);
 
-			Self::deposit_event(RawEvent::Published(from_did, contract, total_amount));
+			Self::deposit_event(RawEvent::Published(from_did, campaign_id, total_amount));
     }
 
-    fn deposit(origin, value: T::Balance, memo: Vec<u8>) {
+    fn deposit(origin, campaign_id: T::Hash, value: T::Balance) {
       let sender = ensure_signed(origin)?;
 
-			let from_did = <did::Module<T>>::identity(sender.clone());
+      ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+			ensure!(value >= Self::min_deposit(), Error::<T>::BelowMinDeposit);
 
-      ensure!(<did::Identity<T>>::exists(sender), "did does not exists");
-			ensure!(value >= Self::min_deposit(), "min deposit 100 pra");
-			ensure!(<AdsRecords<T>>::exists(from_did), "you haven't published ads");
-			
-			let contract_did = <did::Module<T>>::identity(Self::contract());
+			let from_did = <did::Module<T>>::identity(&sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
+			Self::ensure_open(&ads_metadata)?;
 
-			<did::Module<T>>::transfer_by_did(from_did, contract_did, value, memo)?;
+			Self::pay_in(&sender, value)?;
 
 			// update ads records
-			let mut ads_metadata = Self::ads_records(from_did);
-			ads_metadata.total_amount = ads_metadata.total_amount.checked_add(&value).ok_or("overflow")?;
-			ads_metadata.surplus = ads_metadata.surplus.checked_add(&value).ok_or("overflow")?;
+			ads_metadata.total_amount = ads_metadata.total_amount.checked_add(&value).ok_or(Error::<T>::Overflow)?;
+			ads_metadata.surplus = ads_metadata.surplus.checked_add(&value).ok_or(Error::<T>::Overflow)?;
 
-			<AdsRecords<T>>::insert(from_did, ads_metadata);
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
 
-			Self::deposit_event(RawEvent::Deposited(from_did, contract_did, value));
+			Self::deposit_event(RawEvent::Deposited(campaign_id, value));
     }
 
-    fn withdraw(origin, value: T::Balance, memo: Vec<u8>) {
+    fn withdraw(origin, campaign_id: T::Hash, value: T::Balance) {
       let sender = ensure_signed(origin)?;
 
-      let from_did = <did::Module<T>>::identity(sender.clone());
-
-      ensure!(<did::Identity<T>>::exists(sender), "did does not exists");
-			ensure!(<AdsRecords<T>>::exists(from_did), "you haven't published ads");
-			
-			let mut ads_metadata = Self::ads_records(from_did);
+      ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
 
-			ensure!(ads_metadata.surplus >= value, "withdrawl money is larger than your surplus");
+      let from_did = <did::Module<T>>::identity(sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
 
-			let contract_did = <did::Module<T>>::identity(Self::contract());
+			ensure!(ads_metadata.status != CampaignStatus::Suspended, Error::<T>::CampaignFrozen);
+			ensure!(ads_metadata.surplus >= value, Error::<T>::InsufficientSurplus);
 
-			<did::Module<T>>::transfer_by_did(contract_did, from_did, value, memo)?;
+			Self::pay_out(from_did, value)?;
 
 			// update ads metadata
-			ads_metadata.total_amount = ads_metadata.total_amount.checked_sub(&value).ok_or("overflow")?;
-			ads_metadata.surplus = ads_metadata.surplus.checked_sub(&value).ok_or("overflow")?;
+			ads_metadata.total_amount = ads_metadata.total_amount.checked_sub(&value).ok_or(Error::<T>::Overflow)?;
+			ads_metadata.surplus = ads_metadata.surplus.checked_sub(&value).ok_or(Error::<T>::Overflow)?;
 
-			<AdsRecords<T>>::insert(from_did, ads_metadata);
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
 
-			Self::deposit_event(RawEvent::Withdrawl(from_did, value));
+			Self::deposit_event(RawEvent::Withdrawl(campaign_id, value));
     }
 
-		fn distribute(origin, publisher: T::Hash, user: T::Hash, value: T::Balance) {
+		// pay `single_click_fee` per receipt, every receipt is checked before anything is paid
+		fn settle(origin, campaign_id: T::Hash, receipts: Vec<(Receipt<T::Hash, T::Moment>, T::Signature)>) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(sender == Self::contract(), "you have no access to use the funds");
+			ensure!(sender == Self::contract(), Error::<T>::NotContract);
+			ensure!(!receipts.is_empty(), Error::<T>::NoReceipts);
+			ensure!(receipts.len() <= T::MaxReceiptsPerSettle::get() as usize, Error::<T>::TooManyReceipts);
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
 
-			let contract_did = <did::Module<T>>::identity(Self::contract());
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			Self::ensure_payable(&ads_metadata)?;
 
-			ensure!(<AdsRecords<T>>::exists(publisher), "the account hadn't published ads yet");
-      ensure!(<did::Metadata<T>>::exists(user), "the user does not have did yet");
-			
-			let mut ads_metadata = Self::ads_records(publisher);
+			let now = <timestamp::Module<T>>::get();
+			let fee = ads_metadata.single_click_fee;
+			let pacing = ads_metadata.pacing.clone();
 
-			ensure!(ads_metadata.surplus >= value, "your surplus is not enough");
+			let share = ads_metadata.revenue_share.clone();
 
-			<did::Module<T>>::transfer_by_did(contract_did, user, value, "看广告收益".as_bytes().to_vec())?;
+			let mut payouts: Vec<UserPayout<T::Hash, T::Balance, T::Moment>> = Vec::new();
+			// publisher did, amount
+			let mut publishers: Vec<(T::Hash, T::Balance)> = Vec::new();
+			for (receipt, signature) in receipts.iter() {
+				ensure!(receipt.campaign == campaign_id, Error::<T>::WrongCampaign);
+				ensure!(receipt.time >= ads_metadata.create_time && receipt.time <= now, Error::<T>::InvalidReceiptTime);
 
-			// update ads metadata
-			ads_metadata.surplus = ads_metadata.surplus.checked_sub(&value).ok_or("overflow")?;
+				let account = <did::Module<T>>::identity_of(receipt.user).ok_or(Error::<T>::UserNoDid)?;
+				ensure!(signature.verify(&Self::receipt_payload(receipt)[..], &account), Error::<T>::BadSignature);
+
+				let index = match payouts.iter().position(|payout| payout.user == receipt.user) {
+					Some(index) => index,
+					None => {
+						payouts.push(UserPayout {
+							user: receipt.user,
+							nonce: Self::receipt_nonce((campaign_id, receipt.user)),
+							amount: Zero::zero(),
+							superior: <did::Module<T>>::superior_of(receipt.user).filter(|_| share.superior != Perbill::zero()),
+							superior_amount: Zero::zero(),
+							rewards: Self::current_rewards(campaign_id, receipt.user, &pacing, now),
+							receipts: 0,
+						});
+						payouts.len() - 1
+					}
+				};
+				let payout = &mut payouts[index];
+				ensure!(receipt.nonce > payout.nonce, Error::<T>::ReplayedReceipt);
+				ensure!(
+					pacing.max_rewards_per_user == 0 || payout.rewards.count < pacing.max_rewards_per_user,
+					Error::<T>::FrequencyCapReached
+				);
+				payout.nonce = receipt.nonce;
+				payout.rewards.count = payout.rewards.count.saturating_add(1);
+				payout.receipts = payout.receipts.saturating_add(1);
+
+				// split the click fee like `did::transfer_by_did` splits ads proceeds
+				let mut user_cut = fee;
+				if let Some(publisher) = receipt.publisher.clone() {
+					let publisher = <did::Module<T>>::lookup(publisher)?;
+					ensure!(<did::IdentityOf<T>>::exists(publisher), Error::<T>::PublisherNoDid);
+					let cut = share.publisher * fee;
+					if !cut.is_zero() {
+						user_cut = user_cut.saturating_sub(cut);
+						match publishers.iter_mut().find(|(did, _)| *did == publisher) {
+							Some((_, amount)) => *amount = amount.checked_add(&cut).ok_or(Error::<T>::Overflow)?,
+							None => publishers.push((publisher, cut)),
+						}
+					}
+				}
+				if payout.superior.is_some() {
+					let cut = (share.superior * fee).min(user_cut);
+					user_cut = user_cut - cut;
+					payout.superior_amount = payout.superior_amount.checked_add(&cut).ok_or(Error::<T>::Overflow)?;
+				}
+				payout.amount = payout.amount.checked_add(&user_cut).ok_or(Error::<T>::Overflow)?;
+			}
+
+			let mut legs: Vec<T::Balance> = Vec::new();
+			for payout in payouts.iter() {
+				legs.push(payout.amount);
+				legs.push(payout.superior_amount);
+			}
+			legs.extend(publishers.iter().map(|(_, amount)| *amount));
+
+			// the platform fee is charged per leg
+			let mut total: T::Balance = Zero::zero();
+			let mut with_fee: T::Balance = Zero::zero();
+			for amount in legs {
+				total = total.checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+				with_fee = with_fee.checked_add(&amount)
+					.and_then(|sum| sum.checked_add(&(Self::platform_fee() * amount)))
+					.ok_or(Error::<T>::Overflow)?;
+			}
+			ensure!(ads_metadata.surplus >= with_fee, Error::<T>::InsufficientSurplus);
+			Self::ensure_escrowed(with_fee)?;
+			let mut stats = Self::spend(campaign_id, &pacing, now, total)?;
+
+			for payout in payouts {
+				Self::pay_leg(campaign_id, &mut ads_metadata, &mut stats, payout.user, payout.amount)?;
+				<ReceiptNonce<T>>::insert((campaign_id, payout.user), payout.nonce);
+				<UserRewards<T>>::insert((campaign_id, payout.user), payout.rewards);
+				Self::count_user(campaign_id, payout.user, payout.receipts);
+				Self::deposit_event(RawEvent::Distributed(campaign_id, payout.user, payout.amount));
+
+				if let Some(superior) = payout.superior {
+					Self::pay_leg(campaign_id, &mut ads_metadata, &mut stats, superior, payout.superior_amount)?;
+					Self::deposit_event(RawEvent::SuperiorPaid(campaign_id, superior, payout.superior_amount));
+				}
+			}
+			for (publisher, amount) in publishers {
+				Self::pay_leg(campaign_id, &mut ads_metadata, &mut stats, publisher, amount)?;
+				Self::deposit_event(RawEvent::PublisherPaid(campaign_id, publisher, amount));
+			}
+		}
+
+		// lock `total` out of the surplus for users to claim against `root`
+		fn post_root(origin, campaign_id: T::Hash, epoch: u32, root: T::Hash, total: T::Balance) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(sender == Self::contract(), Error::<T>::NotContract);
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+			ensure!(epoch > Self::last_epoch(campaign_id), Error::<T>::StaleEpoch);
+
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			Self::ensure_payable(&ads_metadata)?;
+			let fee = Self::platform_fee() * total;
+			let with_fee = total.checked_add(&fee).ok_or(Error::<T>::Overflow)?;
+			ensure!(ads_metadata.surplus >= with_fee, Error::<T>::InsufficientSurplus);
+			Self::ensure_escrowed(fee)?;
+
+			let now = <timestamp::Module<T>>::get();
+			let deadline = now.checked_add(&T::ClaimPeriod::get()).ok_or(Error::<T>::Overflow)?;
+
+			// the whole locked total counts against today's budget
+			let mut stats = Self::spend(campaign_id, &ads_metadata.pacing, now, total)?;
+
+			// the fee is charged when the root is posted, unclaimed funds come back without it
+			Self::charge_platform_fee(campaign_id, &mut ads_metadata, fee)?;
+			ads_metadata.surplus = ads_metadata.surplus - total;
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::record_spend(campaign_id, &mut stats, total);
+
+			<EpochPayouts<T>>::insert((campaign_id, epoch), EpochPayout {
+				root,
+				total,
+				claimed: Zero::zero(),
+				deadline,
+			});
+			<LastEpoch<T>>::insert(campaign_id, epoch);
+
+			Self::deposit_event(RawEvent::RootPosted(campaign_id, epoch, root, total));
+		}
+
+		fn claim(origin, campaign_id: T::Hash, epoch: u32, value: T::Balance, proof: Vec<T::Hash>) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+
+			let user = <did::Module<T>>::identity(sender);
+			ensure!(<EpochPayouts<T>>::exists((campaign_id, epoch)), Error::<T>::NoEpoch);
+			let mut payout = Self::epoch_payouts((campaign_id, epoch));
+
+			ensure!(!Self::claimed((campaign_id, epoch, user)), Error::<T>::AlreadyClaimed);
+			ensure!(<timestamp::Module<T>>::get() <= payout.deadline, Error::<T>::ClaimExpired);
+
+			let leaf = T::Hashing::hash_of(&(user, value));
+			ensure!(Self::verify_proof(payout.root, leaf, &proof), Error::<T>::InvalidProof);
+
+			let claimed = payout.claimed.checked_add(&value).ok_or(Error::<T>::Overflow)?;
+			ensure!(claimed <= payout.total, Error::<T>::InsufficientSurplus);
+
+			Self::pay_out(user, value)?;
+
+			payout.claimed = claimed;
+			<EpochPayouts<T>>::insert((campaign_id, epoch), payout);
+			<Claimed<T>>::insert((campaign_id, epoch, user), true);
+			<Totals<T>>::mutate(campaign_id, |totals| totals.paid = totals.paid.saturating_add(value));
+			Self::count_user(campaign_id, user, 0);
+
+			Self::deposit_event(RawEvent::Claimed(campaign_id, epoch, user, value));
+		}
+
+		// after the deadline, anyone can return what was not claimed to the campaign
+		fn reclaim(origin, campaign_id: T::Hash, epoch: u32) {
+			let _ = ensure_signed(origin)?;
+
+			ensure!(<EpochPayouts<T>>::exists((campaign_id, epoch)), Error::<T>::NoEpoch);
+			let payout = Self::epoch_payouts((campaign_id, epoch));
+			ensure!(<timestamp::Module<T>>::get() > payout.deadline, Error::<T>::ClaimRunning);
+
+			let unclaimed = payout.total - payout.claimed;
+			let mut ads_metadata = Self::ads_records(campaign_id);
+
+			if ads_metadata.status == CampaignStatus::Expired || ads_metadata.status == CampaignStatus::Rejected {
+				// the surplus was refunded already, so this goes straight to the advertiser
+				Self::pay_out(ads_metadata.owner, unclaimed)?;
+			} else {
+				ads_metadata.surplus = ads_metadata.surplus.checked_add(&unclaimed).ok_or(Error::<T>::Overflow)?;
+				<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+			}
 
-			<AdsRecords<T>>::insert(publisher, ads_metadata);
+			<EpochPayouts<T>>::remove((campaign_id, epoch));
 
-			Self::deposit_event(RawEvent::Distributed(publisher, user, value));
+			Self::deposit_event(RawEvent::Reclaimed(campaign_id, epoch, unclaimed));
 		}
 
-		fn update_ads(origin, single_click_fee: T::Balance, period: T::Moment) {
+		fn update_ads(origin, campaign_id: T::Hash, single_click_fee: T::Balance, period: T::Moment) {
 			let sender = ensure_signed(origin)?;
 
 			let from_did = <did::Module<T>>::identity(sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
+			Self::ensure_open(&ads_metadata)?;
 
-			ensure!(<AdsRecords<T>>::exists(from_did), "you haven't published ads");
+			let end = ads_metadata.create_time.checked_add(&period).ok_or(Error::<T>::Overflow)?;
 
 			// update ads records
-			let mut ads_metadata = Self::ads_records(from_did);
 			ads_metadata.single_click_fee = single_click_fee;
 			ads_metadata.period = period;
 
-			<AdsRecords<T>>::insert(from_did, ads_metadata);
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::schedule_expiry(end, campaign_id);
+
+			Self::deposit_event(RawEvent::AdsUpdated(campaign_id, single_click_fee, period));
+		}
+
+		fn set_tags(origin, campaign_id: T::Hash, tags: Vec<Vec<u8>>) {
+			let sender = ensure_signed(origin)?;
+
+			let from_did = <did::Module<T>>::identity(sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
+			Self::ensure_open(&ads_metadata)?;
+			Self::validate_tags(&tags)?;
+
+			// only approved campaigns are in the tag index
+			if ads_metadata.status == CampaignStatus::Approved {
+				Self::unindex_tags(campaign_id, &ads_metadata.tags);
+				Self::index_tags(campaign_id, &tags);
+			}
+
+			ads_metadata.tags = tags.clone();
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::deposit_event(RawEvent::TagsUpdated(campaign_id, tags));
+		}
+
+		fn set_pacing(origin, campaign_id: T::Hash, max_rewards_per_user: u32, cap_period: T::Moment, daily_budget: T::Balance) {
+			let sender = ensure_signed(origin)?;
+
+			let from_did = <did::Module<T>>::identity(sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
+			Self::ensure_open(&ads_metadata)?;
+
+			ads_metadata.pacing = Pacing { max_rewards_per_user, cap_period, daily_budget };
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::deposit_event(RawEvent::PacingUpdated(campaign_id, max_rewards_per_user, cap_period, daily_budget));
+		}
+
+		// the user gets what is left of each click fee after the publisher and superior shares
+		fn set_revenue_share(origin, campaign_id: T::Hash, publisher: Perbill, superior: Perbill) {
+			let sender = ensure_signed(origin)?;
+
+			let from_did = <did::Module<T>>::identity(sender);
+			let mut ads_metadata = Self::owned_campaign(from_did, campaign_id)?;
+			Self::ensure_open(&ads_metadata)?;
+			ensure!(
+				publisher.deconstruct().saturating_add(superior.deconstruct()) <= Perbill::one().deconstruct(),
+				Error::<T>::InvalidShare
+			);
+
+			ads_metadata.revenue_share = RevenueShare { publisher, superior };
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::deposit_event(RawEvent::RevenueShareSet(campaign_id, publisher, superior));
+		}
+
+		fn set_platform_fee(origin, fee: Perbill) {
+			T::PlatformFeeOrigin::ensure_origin(origin)?;
+
+			<PlatformFee>::put(fee);
+
+			Self::deposit_event(RawEvent::PlatformFeeSet(fee));
+		}
+
+		// refund an expired campaign nobody has closed yet, the caller earns `CloseReward`
+		fn close_expired(origin, campaign_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+
+			let ads_metadata = Self::ads_records(campaign_id);
+			ensure!(ads_metadata.status != CampaignStatus::Suspended, Error::<T>::CampaignFrozen);
+			ensure!(!Self::in_window(&ads_metadata), Error::<T>::CampaignRunning);
+			ensure!(!ads_metadata.surplus.is_zero(), Error::<T>::NothingToRefund);
+
+			let closer = <did::Module<T>>::identity(sender);
+			Self::close(campaign_id, Some(closer))?;
+
+			Self::unschedule_expiry(campaign_id);
+		}
+
+		// pay the surplus a legacy campaign lost in the migration into the escrow and give it back to the campaign
+		fn fund_legacy_surplus(origin, campaign_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<UnfundedSurplus<T>>::exists(campaign_id), Error::<T>::NoUnfundedSurplus);
+			let owed = Self::unfunded_surplus(campaign_id);
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			ads_metadata.surplus = ads_metadata.surplus.checked_add(&owed).ok_or(Error::<T>::Overflow)?;
+
+			Self::pay_in(&sender, owed)?;
+
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+			<UnfundedSurplus<T>>::remove(campaign_id);
+
+			Self::deposit_event(RawEvent::LegacySurplusFunded(campaign_id, owed));
+		}
+
+		fn approve(origin, campaign_id: T::Hash) {
+			T::ModerationOrigin::ensure_origin(origin)?;
+
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			match ads_metadata.status {
+				CampaignStatus::Pending | CampaignStatus::Suspended => {},
+				_ => return Err(Error::<T>::InvalidStatus.into()),
+			}
+
+			let end = ads_metadata.create_time.checked_add(&ads_metadata.period).ok_or(Error::<T>::Overflow)?;
+			ads_metadata.status = CampaignStatus::Approved;
+			Self::index_tags(campaign_id, &ads_metadata.tags);
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			// a suspended campaign left the queue, and expires next block if its window passed meanwhile
+			Self::schedule_expiry(end, campaign_id);
+
+			Self::deposit_event(RawEvent::Approved(campaign_id));
+		}
+
+		fn reject(origin, campaign_id: T::Hash) {
+			T::ModerationOrigin::ensure_origin(origin)?;
+
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			match ads_metadata.status {
+				CampaignStatus::Pending | CampaignStatus::Suspended => {},
+				_ => return Err(Error::<T>::InvalidStatus.into()),
+			}
+			Self::ensure_refundable(&ads_metadata, None)?;
+
+			ads_metadata.status = CampaignStatus::Rejected;
+			Self::unschedule_expiry(campaign_id);
+
+			Self::refund(campaign_id, &mut ads_metadata, None)?;
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::deposit_event(RawEvent::Rejected(campaign_id));
+		}
+
+		fn suspend(origin, campaign_id: T::Hash) {
+			T::ModerationOrigin::ensure_origin(origin)?;
+
+			ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+			let mut ads_metadata = Self::ads_records(campaign_id);
+			ensure!(ads_metadata.status == CampaignStatus::Approved, Error::<T>::InvalidStatus);
+			if T::RefundOnSuspend::get() {
+				Self::ensure_refundable(&ads_metadata, None)?;
+			}
+
+			ads_metadata.status = CampaignStatus::Suspended;
+			Self::unindex_tags(campaign_id, &ads_metadata.tags);
+			Self::unschedule_expiry(campaign_id);
+
+			if T::RefundOnSuspend::get() {
+				Self::refund(campaign_id, &mut ads_metadata, None)?;
+			}
+			<AdsRecords<T>>::insert(campaign_id, ads_metadata);
+
+			Self::deposit_event(RawEvent::Suspended(campaign_id));
+		}
+
+		// the aggregator backs its settlements with a bond fraud reports can slash
+		fn bond(origin, value: T::Balance) {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender == Self::contract(), Error::<T>::NotContract);
+
+			let bond = Self::aggregator_bond().checked_add(&value).ok_or(Error::<T>::Overflow)?;
+			<balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, value)?;
+			<AggregatorBond<T>>::put(bond);
+
+			Self::deposit_event(RawEvent::AggregatorBonded(value));
+		}
+
+		fn unbond(origin, value: T::Balance) {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender == Self::contract(), Error::<T>::NotContract);
+			ensure!(Self::open_payout_reports() == 0, Error::<T>::ReportsOpen);
+
+			let bond = Self::aggregator_bond().checked_sub(&value).ok_or(Error::<T>::InsufficientBond)?;
+			<balances::Module<T> as ReservableCurrency<_>>::unreserve(&sender, value);
+			<AggregatorBond<T>>::put(bond);
+
+			Self::deposit_event(RawEvent::AggregatorUnbonded(value));
+		}
+
+		// reserve `ReportBond` and accuse the aggregator or a user did, `evidence` hashes the proof
+		fn report_fraud(origin, target: ReportTarget<T::Hash>, evidence: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+			// the accused did is fixed now, a name changing hands later doesn't move the slash
+			let target = match target {
+				ReportTarget::Payout(campaign_id) => {
+					ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+					ReportTarget::Payout(campaign_id)
+				},
+				ReportTarget::User(who) => {
+					let did = <did::Module<T>>::lookup(who)?;
+					ensure!(<did::IdentityOf<T>>::exists(did), Error::<T>::UserNoDid);
+					ReportTarget::User(DidOrName::Did(did))
+				},
+			};
+
+			let report_id = Self::next_report_id();
+			let next_id = report_id.checked_add(1).ok_or(Error::<T>::Overflow)?;
+			let bond = T::ReportBond::get();
+			<balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, bond)?;
+
+			if let ReportTarget::Payout(_) = target {
+				<OpenPayoutReports>::mutate(|count| *count = count.saturating_add(1));
+			}
+			<Reports<T>>::insert(report_id, FraudReport {
+				reporter: sender.clone(),
+				target: target.clone(),
+				evidence,
+				bond,
+			});
+			<NextReportId>::put(next_id);
+
+			Self::deposit_event(RawEvent::FraudReported(report_id, sender, target));
+		}
+
+		// slash up to `penalty` from the accused, the reporter gets its bond back and `ReporterReward` of the slash
+		fn confirm_fraud(origin, report_id: u64, penalty: T::Balance) {
+			T::ReportOrigin::ensure_origin(origin)?;
+
+			let report = Self::reports(report_id).ok_or(Error::<T>::NoReport)?;
+			let accused = match report.target.clone() {
+				ReportTarget::User(who) => Some(<did::Module<T>>::lookup(who)?),
+				ReportTarget::Payout(_) => None,
+			};
+			Self::close_report(report_id, &report);
+
+			let slashed = match accused {
+				None => {
+					let value = penalty.min(Self::aggregator_bond());
+					let (imbalance, _) = <balances::Module<T> as ReservableCurrency<_>>::slash_reserved(&Self::contract(), value);
+					<AggregatorBond<T>>::mutate(|bond| *bond = bond.saturating_sub(imbalance.peek()));
+					imbalance
+				},
+				Some(did) => <did::Module<T>>::slash_locked(did, penalty),
+			};
+			<balances::Module<T> as ReservableCurrency<_>>::unreserve(&report.reporter, report.bond);
+
+			let total = slashed.peek();
+			let (reward, rest) = slashed.split(T::ReporterReward::get() * total);
+			let reward_value = reward.peek();
+			<balances::Module<T> as Currency<_>>::resolve_creating(&report.reporter, reward);
+			T::Slash::on_unbalanced(rest);
+
+			Self::deposit_event(RawEvent::FraudConfirmed(report_id, total, reward_value));
+		}
+
+		// the report was false, its bond is forfeited
+		fn dismiss_report(origin, report_id: u64) {
+			T::ReportOrigin::ensure_origin(origin)?;
+
+			let report = Self::reports(report_id).ok_or(Error::<T>::NoReport)?;
+			Self::close_report(report_id, &report);
+
+			let (forfeited, _) = <balances::Module<T> as ReservableCurrency<_>>::slash_reserved(&report.reporter, report.bond);
+			let value = forfeited.peek();
+			T::Slash::on_unbalanced(forfeited);
+
+			Self::deposit_event(RawEvent::ReportDismissed(report_id, value));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// whether `create_time + period` is still ahead
+	fn in_window(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>) -> bool {
+		match ads_metadata.create_time.checked_add(&ads_metadata.period) {
+			Some(end) => <timestamp::Module<T>>::get() < end,
+			None => true,
+		}
+	}
+
+	// whether the campaign can still pay out
+	fn is_running(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>) -> bool {
+		ads_metadata.status == CampaignStatus::Approved && Self::in_window(ads_metadata)
+	}
+
+	// campaigns in review or approved can still be topped up and edited
+	fn ensure_open(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>) -> DispatchResult {
+		match ads_metadata.status {
+			CampaignStatus::Pending | CampaignStatus::Approved => {},
+			CampaignStatus::Suspended => return Err(Error::<T>::CampaignFrozen.into()),
+			CampaignStatus::Rejected => return Err(Error::<T>::InvalidStatus.into()),
+			CampaignStatus::Expired => return Err(Error::<T>::CampaignExpired.into()),
+		}
+		ensure!(Self::in_window(ads_metadata), Error::<T>::CampaignExpired);
+		Ok(())
+	}
+
+	fn ensure_payable(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>) -> DispatchResult {
+		ensure!(ads_metadata.status == CampaignStatus::Approved, Error::<T>::NotApproved);
+		ensure!(Self::in_window(ads_metadata), Error::<T>::CampaignExpired);
+		Ok(())
+	}
+
+	fn close_report(report_id: u64, report: &FraudReport<T::AccountId, T::Hash, T::Balance>) {
+		if let ReportTarget::Payout(_) = report.target {
+			<OpenPayoutReports>::mutate(|count| *count = count.saturating_sub(1));
+		}
+		<Reports<T>>::remove(report_id);
+	}
+
+	// width of an expiry bucket, the expected block time
+	fn expiry_granularity() -> T::Moment {
+		let period = <T as timestamp::Trait>::MinimumPeriod::get();
+		period.saturating_add(period).max(One::one())
+	}
+
+	// queue the campaign in the first bucket at or after `end` that has not been drained yet
+	fn schedule_expiry(end: T::Moment, campaign_id: T::Hash) {
+		Self::unschedule_expiry(campaign_id);
+
+		let granularity = Self::expiry_granularity();
+		let rest = end % granularity;
+		let mut bucket = if rest.is_zero() { end } else { end.saturating_add(granularity - rest) };
+		match Self::next_expiry() {
+			Some(next) => bucket = bucket.max(next),
+			None => {
+				let now = <timestamp::Module<T>>::get();
+				<NextExpiry<T>>::put(now - now % granularity);
+			},
+		}
+
+		<ExpiryQueue<T>>::mutate(bucket, |campaigns| campaigns.push(campaign_id));
+		<ExpiryOf<T>>::insert(campaign_id, bucket);
+	}
+
+	fn unschedule_expiry(campaign_id: T::Hash) {
+		if let Some(bucket) = <ExpiryOf<T>>::take(campaign_id) {
+			let mut campaigns = Self::expiry_queue(bucket);
+			campaigns.retain(|id| *id != campaign_id);
+			if campaigns.is_empty() {
+				<ExpiryQueue<T>>::remove(bucket);
+			} else {
+				<ExpiryQueue<T>>::insert(bucket, campaigns);
+			}
+		}
+	}
+
+	// mark the campaign expired and send the surplus back, minus the closer's reward
+	fn close(campaign_id: T::Hash, closer: Option<T::Hash>) -> DispatchResult {
+		let mut ads_metadata = Self::ads_records(campaign_id);
+		// dispatch is not transactional, so the refund is checked before the campaign is taken down
+		Self::ensure_refundable(&ads_metadata, closer)?;
+
+		if ads_metadata.status != CampaignStatus::Expired {
+			if ads_metadata.status == CampaignStatus::Approved {
+				Self::unindex_tags(campaign_id, &ads_metadata.tags);
+			}
+			ads_metadata.status = CampaignStatus::Expired;
+			Self::deposit_event(RawEvent::Expired(campaign_id));
+		}
+
+		Self::refund(campaign_id, &mut ads_metadata, closer)?;
+		<AdsRecords<T>>::insert(campaign_id, &ads_metadata);
+
+		Ok(())
+	}
+
+	// send the surplus back to the advertiser, minus the closer's reward
+	fn refund(
+		campaign_id: T::Hash,
+		ads_metadata: &mut AdsMetadata<T::Hash, T::Balance, T::Moment>,
+		closer: Option<T::Hash>,
+	) -> DispatchResult {
+		Self::ensure_refundable(ads_metadata, closer)?;
+
+		let reward = Self::close_reward(ads_metadata, closer);
+		let refund = ads_metadata.surplus - reward;
+		if let Some(closer) = closer {
+			Self::pay_out(closer, reward)?;
+		}
+		Self::pay_out(ads_metadata.owner, refund)?;
+		ads_metadata.surplus = Zero::zero();
+
+		Self::deposit_event(RawEvent::Refunded(campaign_id, ads_metadata.owner, refund));
+		Ok(())
+	}
+
+	// the escrow covers the surplus and every did `refund` pays has an account
+	fn ensure_refundable(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>, closer: Option<T::Hash>) -> DispatchResult {
+		Self::ensure_escrowed(ads_metadata.surplus)?;
+
+		let reward = Self::close_reward(ads_metadata, closer);
+		if let Some(closer) = closer {
+			ensure!(reward.is_zero() || <did::IdentityOf<T>>::exists(closer), Error::<T>::UserNoDid);
+		}
+		let refund = ads_metadata.surplus - reward;
+		ensure!(refund.is_zero() || <did::IdentityOf<T>>::exists(ads_metadata.owner), Error::<T>::UserNoDid);
+		Ok(())
+	}
+
+	// what the closer of a campaign gets out of its surplus
+	fn close_reward(ads_metadata: &AdsMetadata<T::Hash, T::Balance, T::Moment>, closer: Option<T::Hash>) -> T::Balance {
+		match closer {
+			Some(_) => ads_metadata.surplus.min(T::CloseReward::get()),
+			None => Zero::zero(),
+		}
+	}
+
+	// move the did-keyed campaigns into `AdsRecords` and their surplus into the escrow, each keeping the did of
+	// its advertiser as campaign id; looks at `MAX_LEGACY_DIDS` dids per block from the upgrade on until done
+	fn migrate_legacy_campaigns() {
+		let escrow = Self::escrow_account();
+		let contract = Self::contract();
+		let cursor = Self::migration_cursor();
+
+		// endow the escrow like genesis does on new chains
+		let minimum = <balances::Module<T> as Currency<_>>::minimum_balance();
+		if cursor == 0 && <balances::Module<T> as Currency<_>>::free_balance(&escrow) < minimum {
+			let endowed = <balances::Module<T> as Currency<_>>::transfer(&contract, &escrow, minimum, ExistenceRequirement::KeepAlive);
+			if endowed.is_err() {
+				print("failed to endow the ads escrow");
+			}
+		}
+
+		let count = <did::Module<T>>::all_did_count();
+		let batch_end = count.min(cursor.saturating_add(MAX_LEGACY_DIDS));
+		for index in cursor..batch_end {
+			let did = match <did::Module<T>>::did_at(index) {
+				Some(did) => did,
+				None => continue,
+			};
+			let key = Self::legacy_record_key(did);
+			let legacy: LegacyAdsMetadata<T::Balance, T::Moment> = match unhashed::get(&key) {
+				Some(legacy) => legacy,
+				None => continue,
+			};
+			unhashed::kill(&key);
+
+			let moved = <balances::Module<T> as Currency<_>>::transfer(&contract, &escrow, legacy.surplus, ExistenceRequirement::KeepAlive);
+			let surplus = if moved.is_ok() {
+				legacy.surplus
+			} else {
+				// the escrow has to match the surplus, the debt waits for `fund_legacy_surplus`
+				<UnfundedSurplus<T>>::insert(did, legacy.surplus);
+				Self::deposit_event(RawEvent::LegacySurplusUnfunded(did, legacy.surplus));
+				Zero::zero()
+			};
+
+			let end = legacy.create_time.saturating_add(legacy.period);
+			<AdsRecords<T>>::insert(did, AdsMetadata {
+				owner: did,
+				advertiser: legacy.advertiser,
+				topic: legacy.topic,
+				total_amount: legacy.total_amount,
+				surplus,
+				gas_fee_used: legacy.gas_fee_used,
+				single_click_fee: legacy.single_click_fee,
+				create_time: legacy.create_time,
+				period: legacy.period,
+				// they were live before moderation existed
+				status: CampaignStatus::Approved,
+				tags: Vec::new(),
+				pacing: Pacing::default(),
+				revenue_share: RevenueShare::default(),
+			});
+			<CampaignsOf<T>>::mutate(did, |campaigns| campaigns.push(did));
+			Self::schedule_expiry(end, did);
+		}
+
+		if batch_end < count {
+			<MigrationCursor>::put(batch_end);
+		} else {
+			<MigrationCursor>::kill();
+			<StorageVersion>::put(STORAGE_VERSION);
+		}
+	}
+
+	// where storage version 0 kept the campaign of a did, hashing the map prefix and the key
+	fn legacy_record_key(did: T::Hash) -> [u8; 32] {
+		let mut key = b"AdsModule AdsRecords".to_vec();
+		did.encode_to(&mut key);
+		blake2_256(&key)
+	}
+
+	/// The account holding all campaign budgets.
+	pub fn escrow_account() -> T::AccountId {
+		MODULE_ID.into_account()
+	}
+
+	// move an advertiser's funds into the escrow
+	fn pay_in(from: &T::AccountId, value: T::Balance) -> DispatchResult {
+		<balances::Module<T> as Currency<_>>::transfer(from, &Self::escrow_account(), value, ExistenceRequirement::KeepAlive)
+	}
+
+	// whether the escrow can pay `value` and stay above the existential deposit
+	fn ensure_escrowed(value: T::Balance) -> DispatchResult {
+		let free = <balances::Module<T> as Currency<_>>::free_balance(&Self::escrow_account());
+		let left = free.checked_sub(&value).ok_or(Error::<T>::InsufficientEscrow)?;
+		ensure!(left >= <balances::Module<T> as Currency<_>>::minimum_balance(), Error::<T>::InsufficientEscrow);
+		Ok(())
+	}
+
+	// pay out of the escrow to the account behind `to_did`, free of transfer fees
+	fn pay_out(to_did: T::Hash, value: T::Balance) -> DispatchResult {
+		if value.is_zero() {
+			return Ok(());
+		}
+		let to = <did::Module<T>>::identity_of(to_did).ok_or(Error::<T>::UserNoDid)?;
+
+		let imbalance = <balances::Module<T> as Currency<_>>::withdraw(
+			&Self::escrow_account(),
+			value,
+			WithdrawReason::Transfer.into(),
+			ExistenceRequirement::KeepAlive,
+		)?;
+		<balances::Module<T> as Currency<_>>::resolve_creating(&to, imbalance);
+
+		Ok(())
+	}
+
+	/// Checks the escrow holds exactly its existential deposit, the surplus of every campaign
+	/// and what is locked for claims.
+	pub fn check_escrow() -> rstd::result::Result<(), &'static str> {
+		let mut escrowed = <balances::Module<T> as Currency<_>>::minimum_balance();
+		for (_, ads_metadata) in <AdsRecords<T>>::enumerate() {
+			escrowed = escrowed.checked_add(&ads_metadata.surplus).ok_or("escrowed funds overflow")?;
+		}
+		for (_, payout) in <EpochPayouts<T>>::enumerate() {
+			escrowed = escrowed.checked_add(&(payout.total - payout.claimed)).ok_or("escrowed funds overflow")?;
+		}
+
+		let balance = <balances::Module<T> as Currency<_>>::total_balance(&Self::escrow_account());
+		ensure!(escrowed == balance, "escrow balance does not match campaign funds");
+
+		Ok(())
+	}
+
+	// pay one leg of a settlement out of the surplus, charging the platform fee on top
+	fn pay_leg(
+		campaign_id: T::Hash,
+		ads_metadata: &mut AdsMetadata<T::Hash, T::Balance, T::Moment>,
+		stats: &mut CampaignStats<T::Balance, T::Moment>,
+		to_did: T::Hash,
+		value: T::Balance,
+	) -> DispatchResult {
+		// dispatch is not transactional, so everything is checked before funds move
+		let fee = Self::platform_fee() * value;
+		let with_fee = value.checked_add(&fee).ok_or(Error::<T>::Overflow)?;
+		ensure!(ads_metadata.surplus >= with_fee, Error::<T>::InsufficientSurplus);
+		Self::ensure_escrowed(with_fee)?;
+
+		Self::pay_out(to_did, value)?;
+
+		ads_metadata.surplus = ads_metadata.surplus - value;
+		Self::charge_platform_fee(campaign_id, ads_metadata, fee)?;
+		<AdsRecords<T>>::insert(campaign_id, &*ads_metadata);
+		Self::record_spend(campaign_id, stats, value);
+		<Totals<T>>::mutate(campaign_id, |totals| totals.paid = totals.paid.saturating_add(value));
+
+		Ok(())
+	}
+
+	// add to today's spend and its bucket in the spend history
+	fn record_spend(campaign_id: T::Hash, stats: &mut CampaignStats<T::Balance, T::Moment>, value: T::Balance) {
+		stats.spent_today = stats.spent_today + value;
+		<Stats<T>>::insert(campaign_id, &*stats);
+
+		let today = stats.day_start;
+		<SpendHistory<T>>::mutate(campaign_id, |history| {
+			match history.last_mut() {
+				Some((day_start, spent)) if *day_start == today => *spent = spent.saturating_add(value),
+				_ => history.push((today, value)),
+			}
+			Self::drop_stale_days(history, today);
+		});
+	}
+
+	// drop the spend of days before the last `StatsHistoryDays` ending with the day of `now`
+	fn drop_stale_days(history: &mut Vec<(T::Moment, T::Balance)>, now: T::Moment) {
+		let day_length = T::DayLength::get();
+		let days = T::Moment::from(T::StatsHistoryDays::get());
+		if days.is_zero() {
+			history.clear();
+			return;
+		}
+		let first_day = (now / day_length).saturating_sub(days - One::one());
+		history.retain(|(day_start, _)| *day_start / day_length >= first_day);
+	}
+
+	// count a payout to a user did, `impressions` being the receipts it was paid for
+	fn count_user(campaign_id: T::Hash, user: T::Hash, impressions: u64) {
+		let first = !Self::rewarded((campaign_id, user));
+		if first {
+			<Rewarded<T>>::insert((campaign_id, user), true);
+		}
+		<Totals<T>>::mutate(campaign_id, |totals| {
+			totals.impressions = totals.impressions.saturating_add(impressions);
+			if first {
+				totals.unique_users = totals.unique_users.saturating_add(1);
+			}
+		});
+	}
+
+	// move a platform fee the caller checked out of the escrow and the surplus
+	fn charge_platform_fee(
+		campaign_id: T::Hash,
+		ads_metadata: &mut AdsMetadata<T::Hash, T::Balance, T::Moment>,
+		fee: T::Balance,
+	) -> DispatchResult {
+		if fee.is_zero() {
+			return Ok(());
+		}
+		ensure!(ads_metadata.surplus >= fee, Error::<T>::InsufficientSurplus);
+
+		let imbalance = <balances::Module<T> as Currency<_>>::withdraw(
+			&Self::escrow_account(),
+			fee,
+			WithdrawReason::Fee.into(),
+			ExistenceRequirement::KeepAlive,
+		)?;
+		T::PlatformFeeDestination::on_unbalanced(imbalance);
+
+		ads_metadata.surplus = ads_metadata.surplus - fee;
+		ads_metadata.gas_fee_used = ads_metadata.gas_fee_used.saturating_add(fee);
+
+		Self::deposit_event(RawEvent::PlatformFeeCharged(campaign_id, fee));
+		Ok(())
+	}
+
+	/// Spend of the campaign, with `spent_today` reset if a new day started.
+	pub fn current_stats(campaign_id: T::Hash, now: T::Moment) -> CampaignStats<T::Balance, T::Moment> {
+		let day_length = T::DayLength::get();
+		let day_start = now - now % day_length;
+
+		let stats = Self::stats(campaign_id);
+		if stats.day_start == day_start {
+			stats
+		} else {
+			CampaignStats { day_start, spent_today: Zero::zero() }
+		}
+	}
+
+	/// Rewards the user did got from the campaign, reset if the cap period is over.
+	pub fn current_rewards(campaign_id: T::Hash, user: T::Hash, pacing: &Pacing<T::Balance, T::Moment>, now: T::Moment)
+		-> RewardCounter<T::Moment>
+	{
+		let rewards = Self::user_rewards((campaign_id, user));
+		match rewards.period_start.checked_add(&pacing.cap_period) {
+			Some(end) if now < end => rewards,
+			_ => RewardCounter { period_start: now, count: 0 },
+		}
+	}
+
+	// today's stats, provided `value` still fits in the daily budget
+	fn spend(campaign_id: T::Hash, pacing: &Pacing<T::Balance, T::Moment>, now: T::Moment, value: T::Balance)
+		-> rstd::result::Result<CampaignStats<T::Balance, T::Moment>, Error<T>>
+	{
+		let stats = Self::current_stats(campaign_id, now);
+		let spent = stats.spent_today.checked_add(&value).ok_or(Error::<T>::Overflow)?;
+		ensure!(pacing.daily_budget.is_zero() || spent <= pacing.daily_budget, Error::<T>::DailyBudgetExceeded);
+
+		Ok(stats)
+	}
+
+	fn validate_tags(tags: &[Vec<u8>]) -> DispatchResult {
+		ensure!(tags.len() <= T::MaxTags::get() as usize, Error::<T>::TooManyTags);
+		for (i, tag) in tags.iter().enumerate() {
+			ensure!(!tag.is_empty() && tag.len() <= T::MaxTagLength::get() as usize, Error::<T>::InvalidTag);
+			ensure!(!tags[..i].contains(tag), Error::<T>::InvalidTag);
+		}
+		Ok(())
+	}
+
+	fn index_tags(campaign_id: T::Hash, tags: &[Vec<u8>]) {
+		for tag in tags {
+			<CampaignsByTag<T>>::mutate(tag, |campaigns| campaigns.push(campaign_id));
+		}
+	}
+
+	fn unindex_tags(campaign_id: T::Hash, tags: &[Vec<u8>]) {
+		for tag in tags {
+			let mut campaigns = Self::campaigns_by_tag(tag);
+			campaigns.retain(|id| *id != campaign_id);
+			if campaigns.is_empty() {
+				<CampaignsByTag<T>>::remove(tag);
+			} else {
+				<CampaignsByTag<T>>::insert(tag, campaigns);
+			}
+		}
+	}
+
+	/// Running campaigns carrying any of `tags`, best paying and then richest first.
+	/// The index holds approved campaigns only, those past their window until they are closed.
+	pub fn active_campaigns(tags: Vec<Vec<u8>>) -> Vec<CampaignInfo<T::Hash, T::Balance>> {
+		let mut ids: Vec<T::Hash> = Vec::new();
+		for tag in tags {
+			for id in Self::campaigns_by_tag(tag) {
+				if !ids.contains(&id) {
+					ids.push(id);
+				}
+			}
+		}
+
+		let mut campaigns: Vec<CampaignInfo<T::Hash, T::Balance>> = ids.into_iter()
+			.map(|id| (id, Self::ads_records(id)))
+			.filter(|(_, ads_metadata)| Self::is_running(ads_metadata))
+			.map(|(id, ads_metadata)| CampaignInfo {
+				campaign_id: id,
+				single_click_fee: ads_metadata.single_click_fee,
+				surplus: ads_metadata.surplus,
+				tags: ads_metadata.tags,
+			})
+			.collect();
+		campaigns.sort_by(|a, b| b.single_click_fee.cmp(&a.single_click_fee).then(b.surplus.cmp(&a.surplus)));
+
+		campaigns
+	}
 
-			Self::deposit_event(RawEvent::AdsUpdated(from_did, single_click_fee, period));
+	/// Totals, remaining budget and daily spend of a campaign.
+	pub fn campaign_report(campaign_id: T::Hash) -> Option<CampaignReport<T::Balance, T::Moment>> {
+		if !<AdsRecords<T>>::exists(campaign_id) {
+			return None;
 		}
+		let totals = Self::totals(campaign_id);
+		// the history is only trimmed on payouts
+		let mut daily_spend = Self::spend_history(campaign_id);
+		Self::drop_stale_days(&mut daily_spend, <timestamp::Module<T>>::get());
+
+		Some(CampaignReport {
+			surplus: Self::ads_records(campaign_id).surplus,
+			paid: totals.paid,
+			impressions: totals.impressions,
+			unique_users: totals.unique_users,
+			daily_spend,
+		})
+	}
+
+	/// The bytes a user signs for `receipt`: `RECEIPT_DOMAIN`, the genesis hash and the encoded receipt.
+	pub fn receipt_payload(receipt: &Receipt<T::Hash, T::Moment>) -> Vec<u8> {
+		let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+		(RECEIPT_DOMAIN, genesis_hash, receipt).encode()
+	}
+
+	// fold the proof over the leaf, hashing each pair in sorted order
+	fn verify_proof(root: T::Hash, leaf: T::Hash, proof: &[T::Hash]) -> bool {
+		let computed = proof.iter().fold(leaf, |node, sibling| {
+			if node.as_ref() <= sibling.as_ref() {
+				T::Hashing::hash_of(&(node, *sibling))
+			} else {
+				T::Hashing::hash_of(&(*sibling, node))
+			}
+		});
+
+		computed == root
+	}
+
+	// the campaign, provided `did` published it
+	fn owned_campaign(did: T::Hash, campaign_id: T::Hash)
+		-> rstd::result::Result<AdsMetadata<T::Hash, T::Balance, T::Moment>, Error<T>>
+	{
+		ensure!(<AdsRecords<T>>::exists(campaign_id), Error::<T>::NotPublished);
+
+		let ads_metadata = Self::ads_records(campaign_id);
+		ensure!(ads_metadata.owner == did, Error::<T>::NotOwner);
+
+		Ok(ads_metadata)
 	}
 }
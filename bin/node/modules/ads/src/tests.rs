@@ -2,12 +2,13 @@
 
 use super::*;
 
+use std::cell::RefCell;
 use support::{assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types};
 use primitives::H256;
 // The testing primitives are very useful for avoiding having to work with signatures
 // or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
 use sp_runtime::{
-  Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup},
+  Perbill, testing::{Header, TestSignature, UintAuthorityId}, traits::{BlakeTwo256, IdentityLookup, OnInitialize, Hash},
 };
 
 impl_outer_origin! {
@@ -53,7 +54,6 @@ impl system::Trait for Test {
   type Version = ();
 }
 parameter_types! {
-  pub const ExistentialDeposit: u64 = 0;
   pub const TransferFee: u64 = 0;
   pub const CreationFee: u64 = 0;
 }
@@ -88,15 +88,67 @@ parameter_types! {
 
 impl did::Trait for Test {
   type Event = Event;
+  type NameResolver = ();
+}
+
+parameter_types! {
+  pub const MaxExpiriesPerBlock: u32 = 2;
+  pub const CloseReward: u64 = 10;
+  pub const MaxReceiptsPerSettle: u32 = 10;
+  pub const ClaimPeriod: u64 = 20;
+  pub const MaxTags: u32 = 3;
+  pub const MaxTagLength: u32 = 8;
+  pub const DayLength: u64 = 100;
+  pub const StatsHistoryDays: u32 = 2;
+  pub const ReportBond: u64 = 50;
+  pub const ReporterReward: Perbill = Perbill::from_percent(20);
 }
 
 impl Trait for Test {
   type Event = Event;
+  type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+  type CloseReward = CloseReward;
+  type Signature = TestSignature;
+  type Public = UintAuthorityId;
+  type MaxReceiptsPerSettle = MaxReceiptsPerSettle;
+  type ClaimPeriod = ClaimPeriod;
+  type MaxTags = MaxTags;
+  type MaxTagLength = MaxTagLength;
+  type DayLength = DayLength;
+  type StatsHistoryDays = StatsHistoryDays;
+  type PlatformFeeOrigin = system::EnsureRoot<u64>;
+  type PlatformFeeDestination = ();
+  type ModerationOrigin = system::EnsureRoot<u64>;
+  type RefundOnSuspend = RefundOnSuspend;
+  type ReportBond = ReportBond;
+  type ReporterReward = ReporterReward;
+  type ReportOrigin = system::EnsureRoot<u64>;
+  type Slash = ();
+}
+
+thread_local! {
+  static REFUND_ON_SUSPEND: RefCell<bool> = RefCell::new(false);
+  static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
+}
+
+pub struct ExistentialDeposit;
+impl Get<u64> for ExistentialDeposit {
+  fn get() -> u64 {
+    EXISTENTIAL_DEPOSIT.with(|v| *v.borrow())
+  }
+}
+
+pub struct RefundOnSuspend;
+impl Get<bool> for RefundOnSuspend {
+  fn get() -> bool {
+    REFUND_ON_SUSPEND.with(|v| *v.borrow())
+  }
 }
 
 type AdsModule = Module<Test>;
 type Balances = balances::Module<Test>;
 type DidModule = did::Module<Test>;
+type Timestamp = timestamp::Module<Test>;
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
@@ -165,6 +217,40 @@ fn prepare_dids_for_test() {
   ));
 }
 
+fn approve_all() {
+  for campaign_id in AdsModule::campaigns_of(DidModule::identity(3)) {
+    assert_ok!(AdsModule::approve(Origin::ROOT, campaign_id));
+  }
+}
+
+fn escrow() -> u64 {
+  Balances::free_balance(&AdsModule::escrow_account())
+}
+
+fn receipt(campaign: H256, who: u64, nonce: u64, time: u64) -> (Receipt<H256, u64>, TestSignature) {
+  sign(Receipt { campaign, user: DidModule::identity(who), publisher: None, nonce, time })
+}
+
+fn published_receipt(campaign: H256, who: u64, publisher: H256, nonce: u64, time: u64)
+  -> (Receipt<H256, u64>, TestSignature)
+{
+  sign(Receipt { campaign, user: DidModule::identity(who), publisher: Some(DidOrName::Did(publisher)), nonce, time })
+}
+
+fn sign(receipt: Receipt<H256, u64>) -> (Receipt<H256, u64>, TestSignature) {
+  let who = DidModule::identity_of(receipt.user).unwrap();
+  let signature = TestSignature(who, AdsModule::receipt_payload(&receipt));
+  (receipt, signature)
+}
+
+fn leaf(who: u64, value: u64) -> H256 {
+  BlakeTwo256::hash_of(&(DidModule::identity(who), value))
+}
+
+fn node(a: H256, b: H256) -> H256 {
+  if a <= b { BlakeTwo256::hash_of(&(a, b)) } else { BlakeTwo256::hash_of(&(b, a)) }
+}
+
 #[test]
 fn should_pass_publish() {
   new_test_ext().execute_with(|| {
@@ -175,16 +261,19 @@ fn should_pass_publish() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       1000,
       1,
       30
     ));
 
     assert_eq!(Balances::free_balance(&3), 9000);
-    assert_eq!(Balances::free_balance(&2), 10000);
+    assert_eq!(escrow(), 1000);
 
     let did = DidModule::identity(3);
-    assert_eq!(AdsModule::ads_records(did), AdsMetadata {
+    let campaign_id = AdsModule::campaigns_of(did)[0];
+    assert_eq!(AdsModule::ads_records(campaign_id), AdsMetadata {
+      owner: did,
       advertiser: b"huawei".to_vec(),
       topic: b"p20 pro".to_vec(),
       total_amount: 1000,
@@ -193,7 +282,13 @@ fn should_pass_publish() {
       single_click_fee: 1,
       create_time: 0,
       period: 30,
+      status: CampaignStatus::Pending,
+      tags: vec![b"phone".to_vec()],
+      pacing: Pacing::default(),
+      revenue_share: RevenueShare::default(),
     });
+    assert_eq!(AdsModule::expiry_queue(30), vec![campaign_id]);
+    assert_eq!(AdsModule::expiry_of(campaign_id), Some(30));
 
   });
 }
@@ -208,10 +303,11 @@ fn less_than_min_deposit_should_not_pass() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       100,
       1,
       30
-    ), "min deposit 500 pra");
+    ), Error::<Test>::BelowMinDeposit);
 
     assert_eq!(Balances::free_balance(&3), 10000);
     assert_eq!(Balances::free_balance(&2), 9000);
@@ -229,19 +325,21 @@ fn should_pass_deposit() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       1000,
       1,
       30
     ));
 
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
     assert_ok!(AdsModule::deposit(
       Origin::signed(3),
-      500,
-      b"new deposit".to_vec()
+      campaign_id,
+      500
     ));
 
     assert_eq!(Balances::free_balance(&3), 8500);
-    assert_eq!(Balances::free_balance(&2), 10500);
+    assert_eq!(escrow(), 1500);
 
   });
 }
@@ -256,25 +354,27 @@ fn should_pass_withdraw() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       1000,
       1,
       30
     ));
 
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
     assert_ok!(AdsModule::withdraw(
       Origin::signed(3),
-      200,
-      b"withdraw money".to_vec()
+      campaign_id,
+      200
     ));
 
     assert_eq!(Balances::free_balance(&3), 9200);
-    assert_eq!(Balances::free_balance(&2), 9800);
+    assert_eq!(escrow(), 800);
 
   });
 }
 
 #[test]
-fn should_pass_distribute() {
+fn should_pass_settle() {
   new_test_ext().execute_with(|| {
     prepare_dids_for_test();
 
@@ -283,29 +383,36 @@ fn should_pass_distribute() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       1000,
-      1,
+      100,
       30
     ));
 
-    let publisher = DidModule::identity(3);
-    let user = DidModule::identity(1);
-    assert_ok!(AdsModule::distribute(
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    Timestamp::set_timestamp(10);
+    assert_ok!(AdsModule::settle(
       Origin::signed(2),
-      publisher,
-      user,
-      200
+      campaign_id,
+      vec![
+        receipt(campaign_id, 1, 1, 5),
+        receipt(campaign_id, 3, 1, 6),
+        receipt(campaign_id, 1, 2, 7),
+      ]
     ));
 
-    assert_eq!(Balances::free_balance(&3), 9000);
-    assert_eq!(Balances::free_balance(&2), 9800);
+    assert_eq!(Balances::free_balance(&3), 9100);
+    assert_eq!(escrow(), 700);
     assert_eq!(Balances::free_balance(&1), 10225);
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 700);
+    assert_eq!(AdsModule::receipt_nonce((campaign_id, DidModule::identity(1))), 2);
 
   });
 }
 
 #[test]
-fn with_no_permission_should_not_pass_distribute() {
+fn with_no_permission_should_not_pass_settle() {
   new_test_ext().execute_with(|| {
     prepare_dids_for_test();
 
@@ -314,23 +421,999 @@ fn with_no_permission_should_not_pass_distribute() {
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
       1000,
       1,
       30
     ));
 
-    let publisher = DidModule::identity(3);
-    let user = DidModule::identity(1);
-    assert_noop!(AdsModule::distribute(
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    assert_noop!(AdsModule::settle(
       Origin::signed(3),
-      publisher,
-      user,
-      200
-    ), "you have no access to use the funds");
+      campaign_id,
+      vec![receipt(campaign_id, 1, 1, 0)]
+    ), Error::<Test>::NotContract);
 
     assert_eq!(Balances::free_balance(&3), 9000);
-    assert_eq!(Balances::free_balance(&2), 10000);
+    assert_eq!(escrow(), 1000);
     assert_eq!(Balances::free_balance(&1), 10025);
 
   });
-}
\ No newline at end of file
+}
+
+#[test]
+fn invalid_receipts_should_not_pass_settle() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      100,
+      30
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    Timestamp::set_timestamp(10);
+
+    // signed by someone else
+    let (forged, _) = receipt(campaign_id, 1, 1, 5);
+    let signature = TestSignature(3, AdsModule::receipt_payload(&forged));
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![(forged.clone(), signature)]),
+      Error::<Test>::BadSignature
+    );
+
+    // signed without the domain and genesis hash, as for another chain or purpose
+    let signature = TestSignature(DidModule::identity_of(forged.user).unwrap(), forged.encode());
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![(forged, signature)]),
+      Error::<Test>::BadSignature
+    );
+
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(H256::zero(), 1, 1, 5)]),
+      Error::<Test>::WrongCampaign
+    );
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 11)]),
+      Error::<Test>::InvalidReceiptTime
+    );
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 5); 11]),
+      Error::<Test>::TooManyReceipts
+    );
+
+    // the same receipt twice in one batch
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 5), receipt(campaign_id, 1, 1, 5)]),
+      Error::<Test>::ReplayedReceipt
+    );
+
+    // or again in a later one
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 5)]));
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 5)]),
+      Error::<Test>::ReplayedReceipt
+    );
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 900);
+  });
+}
+
+#[test]
+fn campaigns_should_be_isolated() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ));
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"mate 30".to_vec(),
+      vec![b"phone".to_vec()],
+      600,
+      300,
+      30
+    ));
+
+    let did = DidModule::identity(3);
+    let campaigns = AdsModule::campaigns_of(did);
+    assert_eq!(campaigns.len(), 2);
+    assert_ne!(campaigns[0], campaigns[1]);
+    assert_eq!(AdsModule::all_ads_count(), 2);
+    assert_eq!(Balances::free_balance(&3), 8400);
+    approve_all();
+
+    // paying out of the second campaign leaves the first one untouched
+    assert_ok!(AdsModule::settle(
+      Origin::signed(2),
+      campaigns[1],
+      vec![receipt(campaigns[1], 1, 1, 0), receipt(campaigns[1], 1, 2, 0)]
+    ));
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaigns[1], vec![receipt(campaigns[1], 1, 3, 0)]),
+      Error::<Test>::InsufficientSurplus
+    );
+    assert_eq!(AdsModule::ads_records(campaigns[0]).surplus, 1000);
+    assert_eq!(AdsModule::ads_records(campaigns[1]).surplus, 0);
+
+    assert_noop!(
+      AdsModule::withdraw(Origin::signed(3), campaigns[1], 100),
+      Error::<Test>::InsufficientSurplus
+    );
+    assert_ok!(AdsModule::withdraw(Origin::signed(3), campaigns[0], 1000));
+
+    // only the owner can touch a campaign
+    assert_noop!(
+      AdsModule::update_ads(Origin::signed(1), campaigns[0], 5, 60),
+      Error::<Test>::NotOwner
+    );
+  });
+}
+
+#[test]
+fn expired_campaign_should_not_pay_out() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+
+    Timestamp::set_timestamp(30);
+
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 1, 20)]),
+      Error::<Test>::CampaignExpired
+    );
+    assert_noop!(
+      AdsModule::deposit(Origin::signed(3), campaign_id, 500),
+      Error::<Test>::CampaignExpired
+    );
+    assert_noop!(
+      AdsModule::update_ads(Origin::signed(3), campaign_id, 1, 60),
+      Error::<Test>::CampaignExpired
+    );
+  });
+}
+
+#[test]
+fn on_initialize_should_refund_expired_campaigns_in_batches() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for period in &[30, 10, 20] {
+      assert_ok!(AdsModule::publish(
+        Origin::signed(3),
+        b"huawei".to_vec(),
+        b"p20 pro".to_vec(),
+        vec![b"phone".to_vec()],
+        500,
+        1,
+        *period
+      ));
+    }
+    let campaigns = AdsModule::campaigns_of(DidModule::identity(3));
+    assert_eq!(AdsModule::expiry_queue(10), vec![campaigns[1]]);
+    assert_eq!(AdsModule::expiry_queue(20), vec![campaigns[2]]);
+    assert_eq!(AdsModule::expiry_queue(30), vec![campaigns[0]]);
+    assert_eq!(Balances::free_balance(&3), 8500);
+
+    // nothing is due yet
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::expiry_of(campaigns[1]), Some(10));
+
+    // extending a campaign moves it to a later bucket
+    assert_ok!(AdsModule::update_ads(Origin::signed(3), campaigns[1], 1, 40));
+    assert!(AdsModule::expiry_queue(10).is_empty());
+    assert_eq!(AdsModule::expiry_queue(40), vec![campaigns[1]]);
+
+    // all due, but at most two per block
+    Timestamp::set_timestamp(50);
+    AdsModule::on_initialize(2);
+    assert!(AdsModule::expiry_of(campaigns[0]).is_none());
+    assert_eq!(AdsModule::expiry_of(campaigns[1]), Some(40));
+    assert_eq!(AdsModule::ads_records(campaigns[2]).status, CampaignStatus::Expired);
+    assert_eq!(AdsModule::ads_records(campaigns[0]).surplus, 0);
+    assert_eq!(Balances::free_balance(&3), 9500);
+    assert_eq!(escrow(), 500);
+
+    AdsModule::on_initialize(3);
+    assert!(AdsModule::expiry_of(campaigns[1]).is_none());
+    assert!(AdsModule::expiry_queue(40).is_empty());
+    assert_eq!(Balances::free_balance(&3), 10000);
+    assert_eq!(escrow(), 0);
+  });
+}
+
+#[test]
+fn should_pass_close_expired() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    assert_noop!(
+      AdsModule::close_expired(Origin::signed(1), campaign_id),
+      Error::<Test>::CampaignRunning
+    );
+
+    approve_all();
+    Timestamp::set_timestamp(30);
+
+    // a refund the escrow can't pay leaves the campaign listed and unexpired
+    let _ = Balances::make_free_balance_be(&AdsModule::escrow_account(), 0);
+    assert_noop!(
+      AdsModule::close_expired(Origin::signed(1), campaign_id),
+      Error::<Test>::InsufficientEscrow
+    );
+    assert_eq!(AdsModule::ads_records(campaign_id).status, CampaignStatus::Approved);
+    let _ = Balances::make_free_balance_be(&AdsModule::escrow_account(), 1000);
+    assert_ok!(AdsModule::close_expired(Origin::signed(1), campaign_id));
+
+    // the closer earns the reward, the rest goes back to the advertiser
+    assert_eq!(Balances::free_balance(&1), 10035);
+    assert_eq!(Balances::free_balance(&3), 9990);
+    assert_eq!(escrow(), 0);
+    assert_eq!(AdsModule::ads_records(campaign_id).status, CampaignStatus::Expired);
+    assert!(AdsModule::expiry_of(campaign_id).is_none());
+
+    assert_noop!(
+      AdsModule::close_expired(Origin::signed(1), campaign_id),
+      Error::<Test>::NothingToRefund
+    );
+  });
+}
+
+#[test]
+fn should_pass_merkle_claims() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      100
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    let (leaf1, leaf3) = (leaf(1, 300), leaf(3, 200));
+    let root = node(leaf1, leaf3);
+
+    assert_noop!(
+      AdsModule::post_root(Origin::signed(3), campaign_id, 1, root, 500),
+      Error::<Test>::NotContract
+    );
+    assert_ok!(AdsModule::post_root(Origin::signed(2), campaign_id, 1, root, 500));
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 500);
+
+    assert_noop!(
+      AdsModule::post_root(Origin::signed(2), campaign_id, 1, root, 100),
+      Error::<Test>::StaleEpoch
+    );
+    assert_noop!(
+      AdsModule::post_root(Origin::signed(2), campaign_id, 2, root, 600),
+      Error::<Test>::InsufficientSurplus
+    );
+
+    assert_ok!(AdsModule::claim(Origin::signed(1), campaign_id, 1, 300, vec![leaf3]));
+    assert_eq!(Balances::free_balance(&1), 10325);
+    assert_eq!(escrow(), 700);
+    assert_noop!(
+      AdsModule::claim(Origin::signed(1), campaign_id, 1, 300, vec![leaf3]),
+      Error::<Test>::AlreadyClaimed
+    );
+
+    // claiming more than the leaf holds
+    assert_noop!(
+      AdsModule::claim(Origin::signed(3), campaign_id, 1, 250, vec![leaf1]),
+      Error::<Test>::InvalidProof
+    );
+    assert_noop!(
+      AdsModule::reclaim(Origin::signed(3), campaign_id, 1),
+      Error::<Test>::ClaimRunning
+    );
+
+    Timestamp::set_timestamp(21);
+    assert_noop!(
+      AdsModule::claim(Origin::signed(3), campaign_id, 1, 200, vec![leaf1]),
+      Error::<Test>::ClaimExpired
+    );
+
+    // the unclaimed 200 goes back to the campaign
+    assert_ok!(AdsModule::reclaim(Origin::signed(3), campaign_id, 1));
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 700);
+    assert_noop!(
+      AdsModule::claim(Origin::signed(3), campaign_id, 1, 200, vec![leaf1]),
+      Error::<Test>::NoEpoch
+    );
+  });
+}
+
+#[test]
+fn should_list_active_campaigns_by_tag() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_noop!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec(), b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ), Error::<Test>::InvalidTag);
+    assert_noop!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()],
+      1000,
+      1,
+      30
+    ), Error::<Test>::TooManyTags);
+
+    for (tags, amount, fee, period) in vec![
+      (vec![b"phone".to_vec()], 500, 1, 30),
+      (vec![b"phone".to_vec(), b"laptop".to_vec()], 600, 2, 30),
+      (vec![b"laptop".to_vec()], 700, 1, 10),
+      (vec![b"phone".to_vec()], 800, 1, 30),
+    ] {
+      assert_ok!(AdsModule::publish(
+        Origin::signed(3),
+        b"huawei".to_vec(),
+        b"p20 pro".to_vec(),
+        tags,
+        amount,
+        fee,
+        period
+      ));
+    }
+    let campaigns = AdsModule::campaigns_of(DidModule::identity(3));
+    // campaigns are indexed once approved
+    assert!(AdsModule::campaigns_by_tag(b"laptop".to_vec()).is_empty());
+    approve_all();
+    assert_eq!(AdsModule::campaigns_by_tag(b"laptop".to_vec()), vec![campaigns[1], campaigns[2]]);
+
+    // highest fee first, then the larger budget
+    let listed = |tags: Vec<Vec<u8>>| -> Vec<H256> {
+      AdsModule::active_campaigns(tags).into_iter().map(|info| info.campaign_id).collect()
+    };
+    assert_eq!(listed(vec![b"phone".to_vec(), b"laptop".to_vec()]), vec![campaigns[1], campaigns[3], campaigns[2], campaigns[0]]);
+    assert_eq!(listed(vec![b"tv".to_vec()]), vec![]);
+
+    // retagging moves the campaign between indexes
+    assert_ok!(AdsModule::set_tags(Origin::signed(3), campaigns[0], vec![b"tv".to_vec()]));
+    assert_eq!(AdsModule::campaigns_by_tag(b"phone".to_vec()), vec![campaigns[1], campaigns[3]]);
+    assert_eq!(listed(vec![b"tv".to_vec()]), vec![campaigns[0]]);
+
+    // expired campaigns drop out of the listing, and out of the index once closed
+    Timestamp::set_timestamp(10);
+    assert_eq!(listed(vec![b"laptop".to_vec()]), vec![campaigns[1]]);
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::campaigns_by_tag(b"laptop".to_vec()), vec![campaigns[1]]);
+
+    // suspended campaigns leave the index until they are approved again
+    assert_ok!(AdsModule::suspend(Origin::ROOT, campaigns[1]));
+    assert!(AdsModule::campaigns_by_tag(b"laptop".to_vec()).is_empty());
+    assert_eq!(AdsModule::campaigns_by_tag(b"phone".to_vec()), vec![campaigns[3]]);
+    assert_ok!(AdsModule::approve(Origin::ROOT, campaigns[1]));
+    assert_eq!(AdsModule::campaigns_by_tag(b"laptop".to_vec()), vec![campaigns[1]]);
+  });
+}
+
+#[test]
+fn should_enforce_frequency_caps_and_daily_budget() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      100,
+      1000
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    assert_noop!(
+      AdsModule::set_pacing(Origin::signed(1), campaign_id, 2, 50, 300),
+      Error::<Test>::NotOwner
+    );
+    // two rewards per user every 50, 300 a day
+    assert_ok!(AdsModule::set_pacing(Origin::signed(3), campaign_id, 2, 50, 300));
+
+    Timestamp::set_timestamp(10);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![
+        receipt(campaign_id, 1, 1, 5),
+        receipt(campaign_id, 1, 2, 6),
+        receipt(campaign_id, 1, 3, 7),
+      ]),
+      Error::<Test>::FrequencyCapReached
+    );
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![
+      receipt(campaign_id, 1, 1, 5),
+      receipt(campaign_id, 1, 2, 6),
+    ]));
+    assert_eq!(AdsModule::user_rewards((campaign_id, DidModule::identity(1))).count, 2);
+
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![
+        receipt(campaign_id, 3, 1, 5),
+        receipt(campaign_id, 3, 2, 6),
+      ]),
+      Error::<Test>::DailyBudgetExceeded
+    );
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 3, 1, 5)]));
+    assert_eq!(AdsModule::stats(campaign_id).spent_today, 300);
+
+    // the cap period is over, but the day is not
+    Timestamp::set_timestamp(60);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 3, 60)]),
+      Error::<Test>::DailyBudgetExceeded
+    );
+
+    // a new day resets the spend
+    Timestamp::set_timestamp(100);
+    assert_eq!(AdsModule::current_stats(campaign_id, 100).spent_today, 0);
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 3, 100)]));
+    assert_eq!(AdsModule::stats(campaign_id), CampaignStats { day_start: 100, spent_today: 100 });
+
+    // roots count against the daily budget as well
+    assert_noop!(
+      AdsModule::post_root(Origin::signed(2), campaign_id, 1, H256::zero(), 250),
+      Error::<Test>::DailyBudgetExceeded
+    );
+    assert_ok!(AdsModule::post_root(Origin::signed(2), campaign_id, 1, H256::zero(), 200));
+    assert_eq!(AdsModule::stats(campaign_id).spent_today, 300);
+  });
+}
+
+#[test]
+fn should_charge_platform_fee() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert!(AdsModule::set_platform_fee(Origin::signed(1), Perbill::from_percent(10)).is_err());
+    assert_ok!(AdsModule::set_platform_fee(Origin::ROOT, Perbill::from_percent(10)));
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      100,
+      30
+    ));
+
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    let issuance = Balances::total_issuance();
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![
+      receipt(campaign_id, 1, 1, 0),
+      receipt(campaign_id, 3, 1, 0),
+    ]));
+
+    // users get the full fee, the campaign pays 10% on top
+    assert_eq!(Balances::free_balance(&1), 10125);
+    assert_eq!(escrow(), 780);
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 780);
+    assert_eq!(AdsModule::ads_records(campaign_id).gas_fee_used, 20);
+    assert_eq!(Balances::total_issuance(), issuance - 20);
+
+    assert_noop!(
+      AdsModule::post_root(Origin::signed(2), campaign_id, 1, H256::zero(), 720),
+      Error::<Test>::InsufficientSurplus
+    );
+    assert_ok!(AdsModule::post_root(Origin::signed(2), campaign_id, 1, H256::zero(), 100));
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 670);
+    assert_eq!(AdsModule::ads_records(campaign_id).gas_fee_used, 30);
+  });
+}
+
+#[test]
+fn escrow_should_match_campaign_funds() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+    assert_ok!(AdsModule::set_platform_fee(Origin::ROOT, Perbill::from_percent(10)));
+
+    for period in &[30, 60] {
+      assert_ok!(AdsModule::publish(
+        Origin::signed(3),
+        b"huawei".to_vec(),
+        b"p20 pro".to_vec(),
+        vec![b"phone".to_vec()],
+        1000,
+        100,
+        *period
+      ));
+    }
+    assert_ok!(AdsModule::check_escrow());
+
+    let campaigns = AdsModule::campaigns_of(DidModule::identity(3));
+    approve_all();
+    assert_ok!(AdsModule::deposit(Origin::signed(3), campaigns[0], 500));
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaigns[0], vec![receipt(campaigns[0], 1, 1, 0)]));
+    assert_ok!(AdsModule::check_escrow());
+
+    let root = node(leaf(1, 300), leaf(3, 200));
+    assert_ok!(AdsModule::post_root(Origin::signed(2), campaigns[1], 1, root, 500));
+    assert_ok!(AdsModule::claim(Origin::signed(1), campaigns[1], 1, 300, vec![leaf(3, 200)]));
+    assert_ok!(AdsModule::withdraw(Origin::signed(3), campaigns[1], 100));
+    assert_ok!(AdsModule::check_escrow());
+
+    // the first campaign expires and is refunded, the unclaimed epoch funds come back later
+    Timestamp::set_timestamp(30);
+    AdsModule::on_initialize(1);
+    assert_ok!(AdsModule::check_escrow());
+
+    Timestamp::set_timestamp(61);
+    AdsModule::on_initialize(2);
+    assert_ok!(AdsModule::reclaim(Origin::signed(1), campaigns[1], 1));
+    assert_ok!(AdsModule::check_escrow());
+    assert_eq!(escrow(), 0);
+
+    // funds sent to the escrow behind the module's back break the invariant
+    let _ = Balances::deposit_creating(&AdsModule::escrow_account(), 1);
+    assert!(AdsModule::check_escrow().is_err());
+  });
+}
+
+#[test]
+fn escrow_should_stay_alive_with_existential_deposit() {
+  EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = 5);
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+    assert_eq!(escrow(), 5);
+    assert_ok!(AdsModule::set_platform_fee(Origin::ROOT, Perbill::from_percent(10)));
+
+    // ten clicks with their fees spend the whole budget
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1100,
+      100,
+      30
+    ));
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    assert_ok!(AdsModule::check_escrow());
+
+    let receipts = (1..=10).map(|nonce| receipt(campaign_id, 1, nonce, 0)).collect();
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, receipts));
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 0);
+    assert_eq!(AdsModule::totals(campaign_id).paid, 1000);
+    assert_eq!(escrow(), 5);
+    assert_ok!(AdsModule::check_escrow());
+
+    // nothing moves when the surplus can't cover a payout and its fee
+    let balance = Balances::free_balance(&1);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 11, 0)]),
+      Error::<Test>::InsufficientSurplus
+    );
+    assert_eq!(Balances::free_balance(&1), balance);
+
+    // the expired campaign refunds nothing and the escrow keeps its deposit
+    Timestamp::set_timestamp(30);
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::ads_records(campaign_id).status, CampaignStatus::Expired);
+    assert_eq!(escrow(), 5);
+    assert_ok!(AdsModule::check_escrow());
+  });
+}
+
+#[test]
+fn should_migrate_legacy_campaigns() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+    let advertiser = DidModule::identity(3);
+    let contract_balance = Balances::free_balance(&2);
+
+    // a campaign of the did-keyed layout, its budget sent to the contract account
+    unhashed::put(&AdsModule::legacy_record_key(advertiser), &LegacyAdsMetadata::<u64, u64> {
+      advertiser: b"huawei".to_vec(),
+      topic: b"p20 pro".to_vec(),
+      total_amount: 1000,
+      surplus: 800,
+      gas_fee_used: 0,
+      single_click_fee: 1,
+      create_time: 0,
+      period: 30,
+    });
+    <StorageVersion>::put(0);
+
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::storage_version(), STORAGE_VERSION);
+    assert!(unhashed::get_raw(&AdsModule::legacy_record_key(advertiser)).is_none());
+
+    // the campaign keeps the advertiser did as its id and its surplus moved to the escrow
+    let ads_metadata = AdsModule::ads_records(advertiser);
+    assert_eq!(ads_metadata.owner, advertiser);
+    assert_eq!(ads_metadata.surplus, 800);
+    assert_eq!(ads_metadata.status, CampaignStatus::Approved);
+    assert_eq!(AdsModule::campaigns_of(advertiser), vec![advertiser]);
+    assert_eq!(Balances::free_balance(&2), contract_balance - 800);
+    assert_eq!(escrow(), 800);
+    assert_ok!(AdsModule::check_escrow());
+
+    assert_ok!(AdsModule::withdraw(Origin::signed(3), advertiser, 100));
+    assert_ok!(AdsModule::settle(Origin::signed(2), advertiser, vec![receipt(advertiser, 1, 1, 0)]));
+    assert_ok!(AdsModule::check_escrow());
+
+    // it expires like any other campaign
+    Timestamp::set_timestamp(30);
+    AdsModule::on_initialize(2);
+    assert_eq!(AdsModule::ads_records(advertiser).status, CampaignStatus::Expired);
+    assert_eq!(escrow(), 0);
+  });
+}
+
+#[test]
+fn should_keep_unfunded_legacy_surplus_owed() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+    let advertiser = DidModule::identity(1);
+    let contract_balance = Balances::free_balance(&2);
+
+    // more than the contract account holds
+    unhashed::put(&AdsModule::legacy_record_key(advertiser), &LegacyAdsMetadata::<u64, u64> {
+      advertiser: b"huawei".to_vec(),
+      topic: b"p20 pro".to_vec(),
+      total_amount: contract_balance,
+      surplus: contract_balance,
+      gas_fee_used: 0,
+      single_click_fee: 1,
+      create_time: 0,
+      period: 30,
+    });
+    <StorageVersion>::put(0);
+
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::storage_version(), STORAGE_VERSION);
+    assert_eq!(AdsModule::migration_cursor(), 0);
+    assert_eq!(AdsModule::ads_records(advertiser).surplus, 0);
+    assert_eq!(AdsModule::unfunded_surplus(advertiser), contract_balance);
+    assert_ok!(AdsModule::check_escrow());
+
+    // the debt is paid in later and returned to the campaign
+    assert_noop!(
+      AdsModule::fund_legacy_surplus(Origin::signed(2), DidModule::identity(3)),
+      Error::<Test>::NoUnfundedSurplus
+    );
+    let _ = Balances::deposit_creating(&2, contract_balance);
+    assert_ok!(AdsModule::fund_legacy_surplus(Origin::signed(2), advertiser));
+    assert_eq!(AdsModule::ads_records(advertiser).surplus, contract_balance);
+    assert!(!<UnfundedSurplus<Test>>::exists(advertiser));
+    assert_ok!(AdsModule::check_escrow());
+  });
+}
+
+#[test]
+fn should_moderate_campaigns() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for topic in [b"p20 pro".to_vec(), b"mate 30".to_vec()].iter() {
+      assert_ok!(AdsModule::publish(
+        Origin::signed(3),
+        b"huawei".to_vec(),
+        topic.clone(),
+        vec![b"phone".to_vec()],
+        1000,
+        1,
+        30
+      ));
+    }
+    let campaigns = AdsModule::campaigns_of(DidModule::identity(3));
+    assert_eq!(Balances::free_balance(&3), 8000);
+
+    // pending campaigns can't pay out and only the moderation origin reviews them
+    assert_eq!(AdsModule::ads_records(campaigns[0]).status, CampaignStatus::Pending);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaigns[0], vec![receipt(campaigns[0], 1, 1, 0)]),
+      Error::<Test>::NotApproved
+    );
+    assert!(AdsModule::approve(Origin::signed(1), campaigns[0]).is_err());
+
+    // rejecting refunds the whole budget
+    assert_ok!(AdsModule::reject(Origin::ROOT, campaigns[0]));
+    assert_eq!(AdsModule::ads_records(campaigns[0]).status, CampaignStatus::Rejected);
+    assert_eq!(AdsModule::ads_records(campaigns[0]).surplus, 0);
+    assert_eq!(Balances::free_balance(&3), 9000);
+    assert!(AdsModule::active_campaigns(vec![b"phone".to_vec()]).is_empty());
+    assert_noop!(AdsModule::approve(Origin::ROOT, campaigns[0]), Error::<Test>::InvalidStatus);
+    assert_noop!(AdsModule::deposit(Origin::signed(3), campaigns[0], 100), Error::<Test>::InvalidStatus);
+
+    assert_ok!(AdsModule::approve(Origin::ROOT, campaigns[1]));
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaigns[1], vec![receipt(campaigns[1], 1, 1, 0)]));
+    assert_eq!(AdsModule::ads_records(campaigns[1]).surplus, 999);
+
+    // suspending freezes the remaining budget
+    assert_ok!(AdsModule::suspend(Origin::ROOT, campaigns[1]));
+    assert_noop!(AdsModule::suspend(Origin::ROOT, campaigns[1]), Error::<Test>::InvalidStatus);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaigns[1], vec![receipt(campaigns[1], 1, 2, 0)]),
+      Error::<Test>::NotApproved
+    );
+    assert_noop!(AdsModule::withdraw(Origin::signed(3), campaigns[1], 100), Error::<Test>::CampaignFrozen);
+
+    // a frozen campaign is left alone when its window passes
+    Timestamp::set_timestamp(30);
+    AdsModule::on_initialize(1);
+    assert_eq!(AdsModule::ads_records(campaigns[1]).status, CampaignStatus::Suspended);
+    assert_eq!(AdsModule::ads_records(campaigns[1]).surplus, 999);
+    assert_noop!(AdsModule::close_expired(Origin::signed(1), campaigns[1]), Error::<Test>::CampaignFrozen);
+
+    // reinstating it queues it again, it expires once the next bucket is due
+    assert_ok!(AdsModule::approve(Origin::ROOT, campaigns[1]));
+    assert_eq!(AdsModule::expiry_of(campaigns[1]), Some(32));
+    Timestamp::set_timestamp(32);
+    AdsModule::on_initialize(2);
+    assert_eq!(AdsModule::ads_records(campaigns[1]).status, CampaignStatus::Expired);
+    assert_eq!(Balances::free_balance(&3), 9999);
+
+    // with `RefundOnSuspend` the budget goes back right away
+    REFUND_ON_SUSPEND.with(|v| *v.borrow_mut() = true);
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p30".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ));
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[2];
+    assert_ok!(AdsModule::approve(Origin::ROOT, campaign_id));
+    assert_ok!(AdsModule::suspend(Origin::ROOT, campaign_id));
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 0);
+    assert_eq!(Balances::free_balance(&3), 9999);
+
+    assert_ok!(AdsModule::check_escrow());
+  });
+}
+
+#[test]
+fn should_adjudicate_fraud_reports() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      30
+    ));
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    let evidence = H256::repeat_byte(1);
+    let locked = Balances::reserved_balance(&2);
+    let free = Balances::free_balance(&1);
+
+    assert_noop!(
+      AdsModule::report_fraud(Origin::signed(1), ReportTarget::Payout(H256::zero()), evidence),
+      Error::<Test>::NotPublished
+    );
+    assert_noop!(
+      AdsModule::report_fraud(Origin::signed(1), ReportTarget::User(DidOrName::Did(H256::zero())), evidence),
+      Error::<Test>::UserNoDid
+    );
+    assert_noop!(
+      AdsModule::report_fraud(Origin::signed(1), ReportTarget::User(DidOrName::Name(b"nobody.pra".to_vec())), evidence),
+      did::Error::<Test>::NameNotResolved
+    );
+
+    // only the aggregator bonds
+    assert_noop!(AdsModule::bond(Origin::signed(1), 500), Error::<Test>::NotContract);
+    assert_ok!(AdsModule::bond(Origin::signed(2), 500));
+    assert_eq!(Balances::reserved_balance(&2), locked + 500);
+
+    assert_ok!(AdsModule::report_fraud(Origin::signed(1), ReportTarget::Payout(campaign_id), evidence));
+    assert_eq!(Balances::reserved_balance(&1), 50);
+    assert_eq!(AdsModule::open_payout_reports(), 1);
+    assert_noop!(AdsModule::unbond(Origin::signed(2), 100), Error::<Test>::ReportsOpen);
+
+    // a confirmed report slashes the bond and rewards the reporter
+    assert!(AdsModule::confirm_fraud(Origin::signed(1), 0, 200).is_err());
+    assert_ok!(AdsModule::confirm_fraud(Origin::ROOT, 0, 200));
+    assert_eq!(AdsModule::aggregator_bond(), 300);
+    assert_eq!(Balances::reserved_balance(&2), locked + 300);
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), free + 40);
+    assert_noop!(AdsModule::confirm_fraud(Origin::ROOT, 0, 200), Error::<Test>::NoReport);
+
+    assert_noop!(AdsModule::unbond(Origin::signed(2), 400), Error::<Test>::InsufficientBond);
+    assert_ok!(AdsModule::unbond(Origin::signed(2), 300));
+    assert_eq!(Balances::reserved_balance(&2), locked);
+
+    // a farming user loses locked funds
+    assert_ok!(AdsModule::report_fraud(Origin::signed(1), ReportTarget::User(DidOrName::Did(DidModule::identity(2))), evidence));
+    assert_ok!(AdsModule::confirm_fraud(Origin::ROOT, 1, 100));
+    assert_eq!(Balances::reserved_balance(&2), locked - 100);
+    assert_eq!(Balances::free_balance(&1), free + 60);
+
+    // a false report forfeits the reporter bond
+    assert_ok!(AdsModule::report_fraud(Origin::signed(1), ReportTarget::User(DidOrName::Did(DidModule::identity(3))), evidence));
+    assert_ok!(AdsModule::dismiss_report(Origin::ROOT, 2));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), free + 10);
+    assert_eq!(AdsModule::reports(2), None);
+
+    assert_ok!(AdsModule::check_escrow());
+  });
+}
+
+#[test]
+fn should_share_revenue_with_publisher_and_superior() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      10,
+      30
+    ));
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+
+    assert_noop!(
+      AdsModule::set_revenue_share(Origin::signed(1), campaign_id, Perbill::from_percent(30), Perbill::from_percent(20)),
+      Error::<Test>::NotOwner
+    );
+    assert_noop!(
+      AdsModule::set_revenue_share(Origin::signed(3), campaign_id, Perbill::from_percent(60), Perbill::from_percent(50)),
+      Error::<Test>::InvalidShare
+    );
+    assert_ok!(AdsModule::set_revenue_share(
+      Origin::signed(3),
+      campaign_id,
+      Perbill::from_percent(30),
+      Perbill::from_percent(20)
+    ));
+
+    let publisher = DidModule::identity(3);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![published_receipt(campaign_id, 2, H256::repeat_byte(9), 1, 0)]),
+      Error::<Test>::PublisherNoDid
+    );
+    let (unnamed, _) = published_receipt(campaign_id, 2, publisher, 1, 0);
+    assert_noop!(
+      AdsModule::settle(Origin::signed(2), campaign_id, vec![sign(Receipt {
+        publisher: Some(DidOrName::Name(b"nobody.pra".to_vec())),
+        ..unnamed
+      })]),
+      did::Error::<Test>::NameNotResolved
+    );
+
+    // the superior of did 2 is the did of account 1
+    let (free_1, free_2, free_3) = (Balances::free_balance(&1), Balances::free_balance(&2), Balances::free_balance(&3));
+    assert_ok!(AdsModule::settle(
+      Origin::signed(2),
+      campaign_id,
+      vec![published_receipt(campaign_id, 2, publisher, 1, 0), receipt(campaign_id, 2, 2, 0)]
+    ));
+    assert_eq!(Balances::free_balance(&2), free_2 + 5 + 8);
+    assert_eq!(Balances::free_balance(&1), free_1 + 2 + 2);
+    assert_eq!(Balances::free_balance(&3), free_3 + 3);
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 980);
+
+    // the genesis did has no superior account, so the user keeps that share
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![published_receipt(campaign_id, 1, publisher, 1, 0)]));
+    assert_eq!(Balances::free_balance(&1), free_1 + 4 + 7);
+    assert_eq!(Balances::free_balance(&3), free_3 + 6);
+    assert_eq!(AdsModule::ads_records(campaign_id).surplus, 970);
+
+    assert_ok!(AdsModule::check_escrow());
+  });
+}
+
+#[test]
+fn should_report_campaign_stats() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      vec![b"phone".to_vec()],
+      1000,
+      1,
+      1000
+    ));
+    let campaign_id = AdsModule::campaigns_of(DidModule::identity(3))[0];
+    approve_all();
+    assert_eq!(AdsModule::campaign_report(H256::zero()), None);
+
+    assert_ok!(AdsModule::settle(
+      Origin::signed(2),
+      campaign_id,
+      vec![receipt(campaign_id, 1, 1, 0), receipt(campaign_id, 1, 2, 0), receipt(campaign_id, 2, 1, 0)]
+    ));
+    assert_eq!(AdsModule::campaign_report(campaign_id), Some(CampaignReport {
+      surplus: 997,
+      paid: 3,
+      impressions: 3,
+      unique_users: 2,
+      daily_spend: vec![(0, 3)],
+    }));
+
+    // returning users are counted once
+    Timestamp::set_timestamp(100);
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 3, 100)]));
+
+    // claims count as payouts, and only the last two days are kept
+    Timestamp::set_timestamp(200);
+    let (leaf1, leaf3) = (leaf(1, 0), leaf(3, 10));
+    assert_ok!(AdsModule::post_root(Origin::signed(2), campaign_id, 1, node(leaf1, leaf3), 10));
+    assert_ok!(AdsModule::claim(Origin::signed(3), campaign_id, 1, 10, vec![leaf1]));
+
+    assert_eq!(AdsModule::campaign_report(campaign_id), Some(CampaignReport {
+      surplus: 986,
+      paid: 14,
+      impressions: 4,
+      unique_users: 3,
+      daily_spend: vec![(100, 1), (200, 10)],
+    }));
+
+    // days are counted by time, not by entries, a payout after a quiet day drops both earlier days
+    Timestamp::set_timestamp(400);
+    assert_ok!(AdsModule::settle(Origin::signed(2), campaign_id, vec![receipt(campaign_id, 1, 4, 400)]));
+    assert_eq!(AdsModule::spend_history(campaign_id), vec![(400, 1)]);
+
+    // and a report leaves out days that passed without payouts
+    Timestamp::set_timestamp(600);
+    assert_eq!(AdsModule::campaign_report(campaign_id).unwrap().daily_spend, vec![]);
+  });
+}
@@ -7,15 +7,38 @@ mod tests;
 use codec::{Decode, Encode};
 use rstd::vec::Vec;
 use support::{
-	decl_event, decl_module, decl_storage, ensure, traits::{Currency, ReservableCurrency, ExistenceRequirement, Get}, dispatch::Result, print,
+	decl_error, decl_event, decl_module, decl_storage, ensure, traits::{Currency, ReservableCurrency, ExistenceRequirement, WithdrawReason, Get, Imbalance}, dispatch::DispatchResult, print,
 };
-use sp_runtime::traits::{CheckedSub, CheckedAdd, Hash, SaturatedConversion};
+use sp_runtime::traits::{Zero, CheckedSub, CheckedAdd, Hash, SaturatedConversion};
 use system::ensure_signed;
 use runtime_io::hashing::blake2_256;
 use harsh::{HarshBuilder};
 
+pub type NegativeImbalanceOf<T> = <balances::Module<T> as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
 pub trait Trait: balances::Trait + timestamp::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Resolves human-readable names (e.g. `alice.pra`) to DIDs.
+	type NameResolver: NameResolver<Self::Hash>;
+}
+
+/// Something that can map a registered name to the DID owning it.
+pub trait NameResolver<Hash> {
+	/// The DID currently holding `name`, if the name is registered and not expired.
+	fn resolve(name: &[u8]) -> Option<Hash>;
+}
+
+impl<Hash> NameResolver<Hash> for () {
+	fn resolve(_name: &[u8]) -> Option<Hash> {
+		None
+	}
+}
+
+/// A DID given either by its hash or by a registered name.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum DidOrName<Hash> {
+	Did(Hash),
+	Name(Vec<u8>),
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -69,6 +92,8 @@ decl_storage! {
 		pub IdentityOf get(identity_of): map T::Hash => Option<T::AccountId>;
 		pub SocialAccount get(social_account): map T::Hash => T::Hash;
 		pub Metadata get(metadata): map T::Hash => MetadataRecord<T::AccountId, T::Hash, T::Balance, T::Moment>;
+		/// Account a did has been offered to, waiting for it to accept.
+		pub PendingUpdate get(pending_update): map T::Hash => Option<T::AccountId>;
 
 		pub AllDidCount get(all_did_count): u64;
 		pub AllDidsArray get(did_by_index): map T::Hash => T::Hash;
@@ -85,17 +110,81 @@ decl_event! {
     <T as timestamp::Trait>::Moment,
     {
         Created(AccountId, Hash),
-        Updated(AccountId, Hash, Balance),
+        UpdateOffered(AccountId, Hash, AccountId),
+        UpdateCancelled(AccountId, Hash),
+        Updated(AccountId, Hash, Balance, Balance),
         Locked(AccountId, Balance, Moment),
         Unlock(AccountId, Balance),
+        /// account, slashed locked funds
+        Slashed(AccountId, Balance),
 				Transfered(Hash, Hash, Balance, Vec<u8>),
 				AddressAdded(AccountId, Vec<u8>, Vec<u8>),
 				GroupNameSet(AccountId, Vec<u8>),
     }
 }
 
+decl_error! {
+	/// Error for the did module.
+	pub enum Error for Module<T: Trait> {
+		/// The did generated from the public key already exists
+		DidAlreadyExists,
+		/// The account is already bound to a did
+		AccountHasDid,
+		/// The account has no did yet
+		NoDid,
+		/// No metadata is stored for the did
+		DidNotFound,
+		/// No account is bound to the did
+		AccountNotFound,
+		/// The social account is already bound to another did
+		SocialAccountBound,
+		/// The superior did does not exist
+		SuperiorNotFound,
+		/// The superior has not locked any funds
+		SuperiorNotLocked,
+		/// The superior has reached its max quota of subordinates
+		SubordinateQuotaExceeded,
+		/// An arithmetic overflow happened
+		Overflow,
+		/// There is no pending update for the did
+		NoPendingUpdate,
+		/// The pending update was offered to another account
+		NotUpdateTarget,
+		/// The free balance is too low
+		InsufficientBalance,
+		/// The reserved balance is too low
+		InsufficientReserved,
+		/// Other modules hold reserved funds of the account, which can't move with the did
+		OtherReserves,
+		/// The new account could not receive the funds of the did
+		CannotReceive,
+		/// The first lock is below the minimum deposit
+		BelowMinDeposit,
+		/// No funds have been locked yet
+		NotLocked,
+		/// The lock period has not passed yet
+		StillLocked,
+		/// Not a valid bitcoin address
+		InvalidBtcAddress,
+		/// Not a valid ethereum address
+		InvalidEthAddress,
+		/// Not a valid eos account
+		InvalidEosAddress,
+		/// The external address type is not one of btc, eth or eos
+		InvalidAddressType,
+		/// The group name is 50 bytes or longer
+		GroupNameTooLong,
+		/// The name is not registered or has expired
+		NameNotResolved,
+		/// Sender and receiver are the same account
+		TransferToSelf,
+	}
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
 		fn deposit_event() = default;
 
 		pub fn create(origin, pubkey: Vec<u8>, address: T::AccountId, did_type: Vec<u8>, superior: T::Hash, social_account: Option<Vec<u8>>, social_superior: Option<Vec<u8>>) {
@@ -106,8 +195,8 @@ decl_module! {
 			let did_hash = T::Hashing::hash(&did_ele);
 
 			// make sure the did is new
-			ensure!(!<Metadata<T>>::exists(&did_hash), "did alread existed");
-			ensure!(!<Identity<T>>::exists(&address), "you already have did");
+			ensure!(!<Metadata<T>>::exists(&did_hash), Error::<T>::DidAlreadyExists);
+			ensure!(!<Identity<T>>::exists(&address), Error::<T>::AccountHasDid);
 
 			let mut superior_did = superior;
 			let mut social_account_hash = None;
@@ -120,26 +209,26 @@ decl_module! {
 				social_account_hash = Some(social_hash);
 
 				// one social account only can bind one did
-				ensure!(!<SocialAccount<T>>::exists(&social_hash), "this social account has been bound");
+				ensure!(!<SocialAccount<T>>::exists(&social_hash), Error::<T>::SocialAccountBound);
 
 				if let Some(mut value) = social_superior {
 					value.append(&mut did_type.to_vec());
 
 					let superior_hash = T::Hashing::hash(&value);
-					ensure!(<SocialAccount<T>>::exists(&superior_hash), "the superior does not exsit");
+					ensure!(<SocialAccount<T>>::exists(&superior_hash), Error::<T>::SuperiorNotFound);
 					superior_did = Self::social_account(superior_hash);
 				};
 			}
 
 			let mut superior_metadata = Self::metadata(superior_did);
 			if superior_metadata.address != Self::genesis_account() && <Metadata<T>>::exists(&superior_did){
-				let subordinate_count = superior_metadata.subordinate_count.checked_add(1).ok_or("overflow")?;
+				let subordinate_count = superior_metadata.subordinate_count.checked_add(1).ok_or(Error::<T>::Overflow)?;
 
-				ensure!(superior_metadata.locked_records.is_some(), "the superior does not locked funds");
+				ensure!(superior_metadata.locked_records.is_some(), Error::<T>::SuperiorNotLocked);
 
 				let locked_records = superior_metadata.locked_records.unwrap();
 				let LockedRecords { max_quota, .. } = locked_records;
-				ensure!(subordinate_count <= max_quota, "the superior's subordinate exceeds max quota");
+				ensure!(subordinate_count <= max_quota, Error::<T>::SubordinateQuotaExceeded);
 
 				superior_metadata.subordinate_count = subordinate_count;
 				superior_metadata.locked_records = Some(locked_records);
@@ -179,7 +268,7 @@ decl_module! {
 			// update did count
 			let all_did_count = Self::all_did_count();
 			let new_count = all_did_count.checked_add(1)
-					.ok_or("Overflow adding a new did")?;
+					.ok_or(Error::<T>::Overflow)?;
 			<AllDidCount>::put(new_count);
 
 			let harsher = HarshBuilder::new().salt("prochain did").length(6).init().unwrap();
@@ -193,43 +282,107 @@ decl_module! {
 			Self::deposit_event(RawEvent::Created(sender, did_hash));
 		}
 
-		pub fn update(origin, to: T::AccountId) {
+		// propose handing the did over to another account, takes effect once `to` accepts
+		pub fn offer_update(origin, to: T::AccountId) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(<Identity<T>>::exists(sender.clone()), "this account has no did yet");
+			ensure!(<Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
 
 			// get current did
 			let did = Self::identity(&sender);
-			ensure!(<Metadata<T>>::exists(did), "did does not exsit");
-			ensure!(!<Identity<T>>::exists(&to), "the public key has been taken");
+			ensure!(<Metadata<T>>::exists(did), Error::<T>::DidNotFound);
+			ensure!(!<Identity<T>>::exists(&to), Error::<T>::AccountHasDid);
+
+			<PendingUpdate<T>>::insert(did, &to);
+
+			Self::deposit_event(RawEvent::UpdateOffered(sender, did, to));
+		}
+
+		// withdraw a pending offer, only possible before it is accepted
+		pub fn cancel_update(origin) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
+
+			let did = Self::identity(&sender);
+			ensure!(<PendingUpdate<T>>::exists(did), Error::<T>::NoPendingUpdate);
+
+			<PendingUpdate<T>>::remove(did);
+
+			Self::deposit_event(RawEvent::UpdateCancelled(sender, did));
+		}
+
+		// accept a pending offer, moves the free balance and the funds reserved by `lock` together
+		// with the did; refused while other modules hold reserves of the account, like a names bid,
+		// an ads report bond or an htlc lock, since their records point at the old account
+		pub fn accept_update(origin, did: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			let to = Self::pending_update(did).ok_or(Error::<T>::NoPendingUpdate)?;
+			ensure!(sender == to, Error::<T>::NotUpdateTarget);
+			ensure!(!<Identity<T>>::exists(&to), Error::<T>::AccountHasDid);
+
+			let from = Self::identity_of(did).ok_or(Error::<T>::AccountNotFound)?;
+			let mut metadata = Self::metadata(did);
+
+			// funds reserved by `lock` have to move along with the locked records
+			let reserved = metadata.locked_records.as_ref()
+				.map(|records| records.locked_funds)
+				.unwrap_or_else(Zero::zero);
+			let reserved_balance = <balances::Module<T>>::reserved_balance(&from);
+			ensure!(reserved_balance >= reserved, Error::<T>::InsufficientReserved);
+			ensure!(reserved_balance == reserved, Error::<T>::OtherReserves);
+
+			let free = <balances::Module<T>>::free_balance(&from)
+				.checked_sub(&T::TransferFee::get())
+				.and_then(|money| money.checked_sub(&T::CreationFee::get()))
+				.ok_or(Error::<T>::InsufficientBalance)?;
+
+			// dispatch is not transactional, so make sure `to` takes both balances before moving any
+			let to_balance = <balances::Module<T>>::total_balance(&to);
+			ensure!(
+				!to_balance.is_zero() || free >= <balances::Module<T> as Currency<_>>::minimum_balance(),
+				Error::<T>::CannotReceive
+			);
+			let to_free = <balances::Module<T>>::free_balance(&to)
+				.checked_add(&free)
+				.ok_or(Error::<T>::Overflow)?;
+			to_balance.checked_add(&free)
+				.and_then(|balance| balance.checked_add(&reserved))
+				.ok_or(Error::<T>::Overflow)?;
+			<balances::Module<T> as Currency<_>>::ensure_can_withdraw(&to, reserved, WithdrawReason::Reserve.into(), to_free)
+				.map_err(|_| Error::<T>::CannotReceive)?;
+
+			// with the checks above `to` exists after the transfer and the reserve moves in full
+			<balances::Module<T> as Currency<_>>::transfer(&from, &to, free, ExistenceRequirement::AllowDeath)?;
+			if !reserved.is_zero() {
+				<balances::Module<T>>::repatriate_reserved(&from, &to, reserved)?;
+				<balances::Module<T>>::reserve(&to, reserved)?;
+			}
 
-			let money = <balances::Module<T>>::free_balance(sender.clone())
-					- T::TransferFee::get()
-					- T::CreationFee::get();
-			<balances::Module<T> as Currency<_>>::transfer(&sender, &to, money, ExistenceRequirement::AllowDeath,)?;
-			
 			// 更新account映射
-			<Identity<T>>::remove(sender.clone());
-			<Identity<T>>::insert(to.clone(), &did);
+			<Identity<T>>::remove(&from);
+			<Identity<T>>::insert(&to, &did);
 
 			// 更新did对应的accountid
-			<IdentityOf<T>>::insert(did.clone(), to.clone());
+			<IdentityOf<T>>::insert(&did, &to);
 
-			let mut metadata = Self::metadata(did);
 			metadata.address = to.clone();
-
 			<Metadata<T>>::insert(did, metadata);
 
-			Self::deposit_event(RawEvent::Updated(to, did, money));
+			<PendingUpdate<T>>::remove(did);
+
+			Self::deposit_event(RawEvent::Updated(to, did, free, reserved));
 		}
 
-		// transfer fund by did
-		pub fn transfer(origin, to_did: T::Hash, value: T::Balance, memo: Vec<u8>) {
+		// transfer fund by did or name
+		pub fn transfer(origin, to: DidOrName<T::Hash>, value: T::Balance, memo: Vec<u8>) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(<Identity<T>>::exists(sender.clone()), "you have no did yet");
+			ensure!(<Identity<T>>::exists(sender.clone()), Error::<T>::NoDid);
 
 			let from_did = Self::identity(sender);
+			let to_did = Self::lookup(to)?;
 			Self::transfer_by_did(from_did, to_did, value, memo)?;
 		}
 
@@ -238,20 +391,20 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 
 			let sender_balance = <balances::Module<T>>::free_balance(sender.clone());
-			ensure!(sender_balance >= value + Self::u128_to_balance(1), "you dont have enough free balance");
-			ensure!(<Identity<T>>::exists(&sender), "this account has no did yet");
+			ensure!(sender_balance >= value + Self::u128_to_balance(1), Error::<T>::InsufficientBalance);
+			ensure!(<Identity<T>>::exists(&sender), Error::<T>::NoDid);
 
 			let did = Self::identity(&sender);
 			let mut metadata = Self::metadata(&did);
 
 			// make sure the superior exists
-			ensure!(<Metadata<T>>::exists(metadata.superior), "superior does not exsit");
+			ensure!(<Metadata<T>>::exists(metadata.superior), Error::<T>::SuperiorNotFound);
 			
 			let locked_funds;
 			let mut rewards_ratio = 20;// basis rewards_ratio is 20%
 
 			if metadata.locked_records.is_none() {
-				ensure!(value >= Self::min_deposit(), "you must lock at least 50 pra first time");
+				ensure!(value >= Self::min_deposit(), Error::<T>::BelowMinDeposit);
 
 				let fee = Self::fee_to_previous();
 
@@ -296,20 +449,20 @@ decl_module! {
 
 			let reserved_balance = <balances::Module<T>>::reserved_balance(sender.clone());
 
-			ensure!(reserved_balance >= value, "unreserved funds should equal or less than reserved funds");
+			ensure!(reserved_balance >= value, Error::<T>::InsufficientReserved);
 
-			ensure!(<Identity<T>>::exists(&sender), "this account has no did yet");
+			ensure!(<Identity<T>>::exists(&sender), Error::<T>::NoDid);
 
 			let did = Self::identity(&sender);
 			let mut metadata = Self::metadata(&did);
-			ensure!(metadata.locked_records.is_some(), "you didn't lock funds before");
+			ensure!(metadata.locked_records.is_some(), Error::<T>::NotLocked);
 			
 			let mut locked_records = metadata.locked_records.unwrap();
 			let LockedRecords { locked_time, locked_period, locked_funds, .. } = locked_records;
 			let now = <timestamp::Module<T>>::get();
-			let unlock_time = locked_time.checked_add(&locked_period).ok_or("Overflow.")?;
+			let unlock_time = locked_time.checked_add(&locked_period).ok_or(Error::<T>::Overflow)?;
 
-			ensure!(now >= unlock_time, "unlock time has not reached");
+			ensure!(now >= unlock_time, Error::<T>::StillLocked);
 
 			let unlock_records = UnlockRecords {
 				unlock_time,
@@ -318,7 +471,7 @@ decl_module! {
 
 			let new_locked_funds = locked_funds - value;
 			let new_max_quota = Self::balance_to_u64(new_locked_funds) * 10;
-			let rewards_ratio = if new_max_quota >= metadata.subordinate_count { 20 } else { 100 * (1 - new_max_quota / metadata.subordinate_count) as u64 };
+			let rewards_ratio = Self::rewards_ratio(new_max_quota, metadata.subordinate_count);
 
 			locked_records = LockedRecords {
 				locked_funds: new_locked_funds,
@@ -341,7 +494,7 @@ decl_module! {
 		fn add_external_address(origin, add_type: Vec<u8>, address: Vec<u8>) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(<Identity<T>>::exists(&sender), "this account has no did yet");
+			ensure!(<Identity<T>>::exists(&sender), Error::<T>::NoDid);
 
 			let did = Self::identity(&sender);
 			let mut metadata = Self::metadata(&did);
@@ -349,21 +502,21 @@ decl_module! {
 
 						match &add_type[..] {
 								b"btc" => {
-										check::from(address.clone()).map_err(|_| "invlid bitcoin address")?;
+										check::from(address.clone()).map_err(|_| Error::<T>::InvalidBtcAddress)?;
 										external_address.btc = address.clone();
 										print("add btc address sucessfully");
 								},
 								b"eth" => {
-										ensure!(check::is_valid_eth_address(address.clone()), "invlid eth account");
+										ensure!(check::is_valid_eth_address(address.clone()), Error::<T>::InvalidEthAddress);
 										external_address.eth = address.clone();
 										print("add eth address sucessfully");
 								},
 								b"eos" => {
-										ensure!(check::is_valid_eos_address(address.clone()), "invlid eos account");
+										ensure!(check::is_valid_eos_address(address.clone()), Error::<T>::InvalidEosAddress);
 										external_address.eos = address.clone();
 										print("add eos address sucessfully");
 								},
-								_ => ensure!(false, "invlid type"),
+								_ => Err(Error::<T>::InvalidAddressType)?,
 						};
 
 			metadata.external_address = external_address;
@@ -376,13 +529,13 @@ decl_module! {
 		fn set_group_name(origin, name: Vec<u8>) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(<Identity<T>>::exists(&sender), "this account has no did yet");
+			ensure!(<Identity<T>>::exists(&sender), Error::<T>::NoDid);
 
 			let did = Self::identity(&sender);
 			let mut metadata = Self::metadata(&did);
 
-			ensure!(name.len() < 50, "group name is too long");
-			ensure!(metadata.locked_records.is_some(), "you are not eligible to set group name");
+			ensure!(name.len() < 50, Error::<T>::GroupNameTooLong);
+			ensure!(metadata.locked_records.is_some(), Error::<T>::NotLocked);
 
 			metadata.group_name = Some(name.clone());
 
@@ -433,24 +586,76 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> Module<T> {
-	pub fn transfer_by_did(from_did: T::Hash, to_did: T::Hash, value: T::Balance, memo: Vec<u8>) -> Result {
-		let from_address = Self::identity_of(&from_did).ok_or("corresponding AccountId does not find")?;
+	/// The did created `index`-th, counting from zero.
+	pub fn did_at(index: u64) -> Option<T::Hash> {
+		let harsher = HarshBuilder::new().salt("prochain did").length(6).init().ok()?;
+		let idx_hash = T::Hashing::hash(&harsher.encode(&[index])?);
+		if <AllDidsArray<T>>::exists(&idx_hash) { Some(Self::did_by_index(idx_hash)) } else { None }
+	}
+
+	/// The superior of a did, if it is bound to an account.
+	pub fn superior_of(did: T::Hash) -> Option<T::Hash> {
+		let MetadataRecord { superior, .. } = Self::metadata(&did);
+		if <IdentityOf<T>>::exists(&superior) { Some(superior) } else { None }
+	}
+
+	// rewards ratio of a did after its locked funds changed, as `unlock` sets it
+	fn rewards_ratio(max_quota: u64, subordinate_count: u64) -> u64 {
+		if max_quota >= subordinate_count { 20 } else { 100 * (1 - max_quota / subordinate_count) as u64 }
+	}
+
+	/// Slash up to `value` of the funds a did has locked, lowering its quota and rewards ratio to match.
+	pub fn slash_locked(did: T::Hash, value: T::Balance) -> NegativeImbalanceOf<T> {
+		let who = match Self::identity_of(&did) {
+			Some(who) => who,
+			None => return NegativeImbalanceOf::<T>::zero(),
+		};
+		let mut metadata = Self::metadata(&did);
+		let mut locked_records = match metadata.locked_records {
+			Some(locked_records) => locked_records,
+			None => return NegativeImbalanceOf::<T>::zero(),
+		};
+
+		let value = value.min(locked_records.locked_funds);
+		let (imbalance, _) = <balances::Module<T> as ReservableCurrency<_>>::slash_reserved(&who, value);
+		let slashed = imbalance.peek();
+
+		locked_records.locked_funds = locked_records.locked_funds - slashed;
+		locked_records.max_quota = Self::balance_to_u64(locked_records.locked_funds) * 10;
+		locked_records.rewards_ratio = Self::rewards_ratio(locked_records.max_quota, metadata.subordinate_count);
+		metadata.locked_records = Some(locked_records);
+		<Metadata<T>>::insert(&did, metadata);
+
+		Self::deposit_event(RawEvent::Slashed(who, slashed));
+		imbalance
+	}
+
+	/// Turn a DID hash or a registered name into the DID hash.
+	pub fn lookup(who: DidOrName<T::Hash>) -> rstd::result::Result<T::Hash, Error<T>> {
+		match who {
+			DidOrName::Did(did) => Ok(did),
+			DidOrName::Name(name) => T::NameResolver::resolve(&name).ok_or(Error::<T>::NameNotResolved),
+		}
+	}
+
+	pub fn transfer_by_did(from_did: T::Hash, to_did: T::Hash, value: T::Balance, memo: Vec<u8>) -> DispatchResult {
+		let from_address = Self::identity_of(&from_did).ok_or(Error::<T>::AccountNotFound)?;
 		let sender_balance = <balances::Module<T>>::free_balance(from_address.clone());
-		ensure!(sender_balance >= value, "you dont have enough free balance");
+		ensure!(sender_balance >= value, Error::<T>::InsufficientBalance);
 
-		let to_address = Self::identity_of(to_did).ok_or("corresponding AccountId does not exsit")?;
-		ensure!(from_address != to_address, "you can not send money to yourself");
+		let to_address = Self::identity_of(to_did).ok_or(Error::<T>::AccountNotFound)?;
+		ensure!(from_address != to_address, Error::<T>::TransferToSelf);
 		
 		// check overflow
 		let receiver_balance = <balances::Module<T>>::free_balance(to_address.clone());
-		sender_balance.checked_sub(&value).ok_or("overflow in calculating balance")?;
-		receiver_balance.checked_add(&value).ok_or("overflow in calculating balance")?;
+		sender_balance.checked_sub(&value).ok_or(Error::<T>::Overflow)?;
+		receiver_balance.checked_add(&value).ok_or(Error::<T>::Overflow)?;
 
 		// proceeds split
 		let fee_type = b"ads";
 		if Self::is_sub(&memo, fee_type) {
 			let MetadataRecord { superior, .. } = Self::metadata(&to_did);
-			let superior_address = Self::identity_of(superior).ok_or("superior AccountId does not find")?;
+			let superior_address = Self::identity_of(superior).ok_or(Error::<T>::AccountNotFound)?;
 			
 			let MetadataRecord { locked_records, ..} = Self::metadata(&superior);
 			let rewards_ratio = if locked_records.is_some() { locked_records.unwrap().rewards_ratio } else { 0 };
@@ -88,6 +88,7 @@ parameter_types! {
 
 impl Trait for Test {
   type Event = Event;
+  type NameResolver = ();
 }
 
 const EOS_ADDRESS: &[u8; 12] = b"praqianchang";
@@ -170,9 +171,73 @@ fn should_pass_update() {
       None
     ));
 
-    assert_ok!(DidModule::update(Origin::signed(1), 2u64));
-    assert_eq!(Balances::free_balance(&1), 0);
-    assert_eq!(Balances::free_balance(&2), 20000);
+    assert_ok!(DidModule::create(
+      Origin::signed(1),
+      b"0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d".to_vec(),
+      2u64,
+      "1".as_bytes().to_vec(),
+      H256::zero(),
+      Some("s".as_bytes().to_vec()),
+      Some("f".as_bytes().to_vec())
+    ));
+
+    assert_ok!(DidModule::lock(Origin::signed(2), 100, 5));
+    assert_eq!(Balances::reserved_balance(&2), 75);
+
+    let did = DidModule::identity(&2);
+    assert_ok!(DidModule::offer_update(Origin::signed(2), 5u64));
+
+    // nothing moves until the new account accepts
+    assert_eq!(Balances::free_balance(&2), 9900);
+    assert_noop!(DidModule::accept_update(Origin::signed(4), did), Error::<Test>::NotUpdateTarget);
+
+    // reserves other modules hold would be stranded on the old account
+    assert_ok!(Balances::reserve(&2, 10));
+    assert_noop!(DidModule::accept_update(Origin::signed(5), did), Error::<Test>::OtherReserves);
+    Balances::unreserve(&2, 10);
+
+    assert_ok!(DidModule::accept_update(Origin::signed(5), did));
+    assert_eq!(Balances::free_balance(&2), 0);
+    assert_eq!(Balances::reserved_balance(&2), 0);
+    assert_eq!(Balances::free_balance(&5), 19900);
+    assert_eq!(Balances::reserved_balance(&5), 75);
+
+    assert_eq!(DidModule::identity(&5), did);
+    assert_eq!(DidModule::identity_of(did), Some(5u64));
+    assert!(!<Identity<Test>>::exists(&2));
+    assert_eq!(DidModule::pending_update(did), None);
+
+    // the new account can unlock what the old one locked
+    Timestamp::set_timestamp(10);
+    assert_ok!(DidModule::unlock(Origin::signed(5), 75));
+    assert_eq!(Balances::free_balance(&5), 19975);
+  });
+}
+
+#[test]
+fn should_pass_cancel_update() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(0);
+
+    assert_ok!(DidModule::create(
+      Origin::signed(1),
+      b"0x22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f".to_vec(),
+      1u64,
+      "1".as_bytes().to_vec(),
+      H256::zero(),
+      Some("f".as_bytes().to_vec()),
+      None
+    ));
+
+    let did = DidModule::identity(&1);
+    assert_noop!(DidModule::cancel_update(Origin::signed(1)), Error::<Test>::NoPendingUpdate);
+
+    assert_ok!(DidModule::offer_update(Origin::signed(1), 2u64));
+    assert_ok!(DidModule::cancel_update(Origin::signed(1)));
+
+    assert_noop!(DidModule::accept_update(Origin::signed(2), did), Error::<Test>::NoPendingUpdate);
+    assert_eq!(Balances::free_balance(&1), 10000);
+    assert_eq!(DidModule::identity_of(did), Some(1u64));
   });
 }
 
@@ -201,7 +266,7 @@ fn should_pass_lock() {
       Some("init".as_bytes().to_vec())
     ));
 
-    assert_noop!(DidModule::lock(Origin::signed(2), 10, 5), "you must lock at least 50 pra first time");
+    assert_noop!(DidModule::lock(Origin::signed(2), 10, 5), Error::<Test>::BelowMinDeposit);
 
     assert_ok!(DidModule::lock(Origin::signed(2), 100, 5));
 
@@ -275,6 +340,63 @@ fn should_pass_unlock() {
   });
 }
 
+#[test]
+fn should_slash_locked_funds() {
+  new_test_ext().execute_with(|| {
+    assert_ok!(DidModule::create(
+      Origin::signed(1),
+      b"0x22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f".to_vec(),
+      1u64,
+      "1".as_bytes().to_vec(),
+      H256::zero(),
+      Some("f".as_bytes().to_vec()),
+      None
+    ));
+
+    assert_ok!(DidModule::create(
+      Origin::signed(1),
+      b"0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d".to_vec(),
+      2u64,
+      "1".as_bytes().to_vec(),
+      H256::zero(),
+      Some("s".as_bytes().to_vec()),
+      Some("f".as_bytes().to_vec())
+    ));
+
+    let did = DidModule::identity(&2);
+    assert_eq!(DidModule::slash_locked(did, 10).peek(), 0);
+
+    assert_ok!(DidModule::lock(Origin::signed(2), 100, 5));
+    assert_eq!(Balances::reserved_balance(&2), 75);
+
+    assert_ok!(DidModule::create(
+      Origin::signed(1),
+      b"0x5e9c79234b5e55348fc60f38b28c2cc60d8bb4bd2862eae2179a05ec39e62658".to_vec(),
+      3u64,
+      "1".as_bytes().to_vec(),
+      H256::zero(),
+      Some("n".as_bytes().to_vec()),
+      Some("s".as_bytes().to_vec())
+    ));
+    assert_eq!(DidModule::metadata(did).subordinate_count, 1);
+
+    assert_eq!(DidModule::slash_locked(did, 50).peek(), 50);
+    assert_eq!(Balances::reserved_balance(&2), 25);
+    let locked_records = DidModule::metadata(did).locked_records.unwrap();
+    assert_eq!(locked_records.locked_funds, 25);
+    assert_eq!(locked_records.max_quota, 250);
+    assert_eq!(locked_records.rewards_ratio, 20);
+
+    // never more than what is locked, the rewards ratio follows the quota like on unlock
+    assert_eq!(DidModule::slash_locked(did, 100).peek(), 25);
+    assert_eq!(Balances::reserved_balance(&2), 0);
+    assert_eq!(Balances::free_balance(&2), 9900);
+    let locked_records = DidModule::metadata(did).locked_records.unwrap();
+    assert_eq!(locked_records.max_quota, 0);
+    assert_eq!(locked_records.rewards_ratio, 100);
+  });
+}
+
 #[test]
 fn should_pass_transfer() {
   new_test_ext().execute_with(|| {
@@ -303,7 +425,7 @@ fn should_pass_transfer() {
     let memo =b"transfer test";
     assert_ok!(DidModule::transfer(
       Origin::signed(2), 
-      DidModule::identity(&1), 
+      DidOrName::Did(DidModule::identity(&1)), 
       100, 
       memo.to_vec()
     ));
@@ -338,7 +460,7 @@ fn should_pass_transfer() {
     // test ads fee split
     assert_ok!(DidModule::transfer(
       Origin::signed(1), 
-      DidModule::identity(&3), 
+      DidOrName::Did(DidModule::identity(&3)), 
       1000, 
       b"ads fee".to_vec()
     ));
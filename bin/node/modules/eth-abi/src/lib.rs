@@ -0,0 +1,235 @@
+//! Decoding of ethereum event logs from the contract ABI, usable in the runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod tests;
+
+use rstd::vec::Vec;
+use primitives::{H160, H256, U256};
+use runtime_io::hashing::keccak_256;
+
+const WORD: usize = 32;
+
+/// Solidity type of an event parameter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParamType {
+	Address,
+	Bool,
+	/// `uintN`, N in bits
+	Uint(usize),
+	/// `bytesN`, N in bytes
+	FixedBytes(usize),
+	Bytes,
+	String,
+}
+
+impl ParamType {
+	fn is_dynamic(&self) -> bool {
+		match self {
+			ParamType::Bytes | ParamType::String => true,
+			_ => false,
+		}
+	}
+
+	fn write_name(&self, out: &mut Vec<u8>) {
+		match self {
+			ParamType::Address => out.extend_from_slice(b"address"),
+			ParamType::Bool => out.extend_from_slice(b"bool"),
+			ParamType::Uint(bits) => {
+				out.extend_from_slice(b"uint");
+				write_decimal(*bits, out);
+			},
+			ParamType::FixedBytes(len) => {
+				out.extend_from_slice(b"bytes");
+				write_decimal(*len, out);
+			},
+			ParamType::Bytes => out.extend_from_slice(b"bytes"),
+			ParamType::String => out.extend_from_slice(b"string"),
+		}
+	}
+}
+
+/// A parameter of an event definition.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventParam {
+	pub name: &'static str,
+	pub kind: ParamType,
+	/// indexed parameters are in the topics, the others in the data
+	pub indexed: bool,
+}
+
+/// An event as declared in the contract, e.g.
+/// `event Claimed(address indexed _msgSender, bytes32 _randomNumber)`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Event {
+	pub name: &'static str,
+	pub inputs: &'static [EventParam],
+}
+
+/// A decoded value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Token {
+	Address(H160),
+	Bool(bool),
+	Uint(U256),
+	FixedBytes(Vec<u8>),
+	Bytes(Vec<u8>),
+	String(Vec<u8>),
+}
+
+impl Token {
+	pub fn into_address(self) -> Option<H160> {
+		match self {
+			Token::Address(address) => Some(address),
+			_ => None,
+		}
+	}
+
+	pub fn into_uint(self) -> Option<U256> {
+		match self {
+			Token::Uint(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// `bytes32` values and indexed dynamic values, which are only in the log as their keccak hash.
+	pub fn into_h256(self) -> Option<H256> {
+		match self {
+			Token::FixedBytes(ref bytes) if bytes.len() == WORD => Some(H256::from_slice(bytes)),
+			_ => None,
+		}
+	}
+
+	/// The bytes of `bytes` and `string` values.
+	pub fn into_bytes(self) -> Option<Vec<u8>> {
+		match self {
+			Token::Bytes(bytes) | Token::String(bytes) => Some(bytes),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+	/// topic0 is not the signature hash of the event
+	SignatureMismatch,
+	/// The log has another number of topics than the event has indexed parameters
+	TopicCount,
+	/// The data ends before all parameters are read
+	DataTooShort,
+	/// A value is out of range for its type, or an offset points outside the data
+	InvalidValue,
+}
+
+impl Event {
+	/// The canonical signature, e.g. `Claimed(address,bytes32)`.
+	pub fn signature(&self) -> Vec<u8> {
+		let mut signature = self.name.as_bytes().to_vec();
+		signature.push(b'(');
+		for (i, input) in self.inputs.iter().enumerate() {
+			if i > 0 {
+				signature.push(b',');
+			}
+			input.kind.write_name(&mut signature);
+		}
+		signature.push(b')');
+		signature
+	}
+
+	/// topic0 of the event's logs, keccak256 of the signature.
+	pub fn topic(&self) -> H256 {
+		H256::from(keccak_256(&self.signature()))
+	}
+
+	/// The values of all parameters in declaration order.
+	pub fn decode(&self, topics: &[H256], data: &[u8]) -> Result<Vec<Token>, Error> {
+		if topics.first() != Some(&self.topic()) {
+			return Err(Error::SignatureMismatch);
+		}
+		let indexed = self.inputs.iter().filter(|input| input.indexed).count();
+		if topics.len() != indexed + 1 {
+			return Err(Error::TopicCount);
+		}
+
+		let mut topics = topics[1..].iter();
+		let mut head = 0;
+		self.inputs.iter().map(|input| {
+			if input.indexed {
+				let topic = topics.next().ok_or(Error::TopicCount)?;
+				if input.kind.is_dynamic() {
+					return Ok(Token::FixedBytes(topic.as_bytes().to_vec()));
+				}
+				return decode_static(input.kind, topic.as_bytes());
+			}
+
+			let word = read_word(data, head)?;
+			head += WORD;
+			if input.kind.is_dynamic() {
+				let offset = to_usize(word)?;
+				let len = to_usize(read_word(data, offset)?)?;
+				let start = offset.checked_add(WORD).ok_or(Error::InvalidValue)?;
+				let end = start.checked_add(len).ok_or(Error::InvalidValue)?;
+				let bytes = data.get(start..end).ok_or(Error::DataTooShort)?.to_vec();
+				Ok(match input.kind {
+					ParamType::String => Token::String(bytes),
+					_ => Token::Bytes(bytes),
+				})
+			} else {
+				decode_static(input.kind, word)
+			}
+		}).collect()
+	}
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8], Error> {
+	let end = at.checked_add(WORD).ok_or(Error::InvalidValue)?;
+	data.get(at..end).ok_or(Error::DataTooShort)
+}
+
+// offsets and lengths, which must fit the data anyway
+fn to_usize(word: &[u8]) -> Result<usize, Error> {
+	let value = U256::from_big_endian(word);
+	if value > U256::from(u32::max_value()) {
+		return Err(Error::InvalidValue);
+	}
+	Ok(value.low_u32() as usize)
+}
+
+fn decode_static(kind: ParamType, word: &[u8]) -> Result<Token, Error> {
+	let padding_is_zero = |len: usize| word[..WORD - len].iter().all(|b| *b == 0);
+	match kind {
+		ParamType::Address => {
+			if !padding_is_zero(20) {
+				return Err(Error::InvalidValue);
+			}
+			Ok(Token::Address(H160::from_slice(&word[WORD - 20..])))
+		},
+		ParamType::Bool => match U256::from_big_endian(word) {
+			value if value.is_zero() => Ok(Token::Bool(false)),
+			value if value == U256::one() => Ok(Token::Bool(true)),
+			_ => Err(Error::InvalidValue),
+		},
+		ParamType::Uint(bits) => {
+			let value = U256::from_big_endian(word);
+			if bits < 256 && value.bits() > bits {
+				return Err(Error::InvalidValue);
+			}
+			Ok(Token::Uint(value))
+		},
+		// bytesN is left aligned
+		ParamType::FixedBytes(len) => {
+			if len > WORD || word[len..].iter().any(|b| *b != 0) {
+				return Err(Error::InvalidValue);
+			}
+			Ok(Token::FixedBytes(word[..len].to_vec()))
+		},
+		ParamType::Bytes | ParamType::String => Err(Error::InvalidValue),
+	}
+}
+
+fn write_decimal(n: usize, out: &mut Vec<u8>) {
+	if n >= 10 {
+		write_decimal(n / 10, out);
+	}
+	out.push(b'0' + (n % 10) as u8);
+}
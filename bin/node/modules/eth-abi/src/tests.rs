@@ -0,0 +1,108 @@
+#![cfg(test)]
+
+use super::*;
+
+// event HTLC(address indexed _msgSender, address indexed _recipientAddr, bytes32 indexed _swapID, bytes32 _randomNumberHash,
+//   uint64 _timestamp, uint256 _expireHeight, uint256 _outAmount, uint256 _praAmount, string _receiverAddr) of ERC20HTLC.sol
+const HTLC: Event = Event {
+  name: "HTLC",
+  inputs: &[
+    EventParam { name: "_msgSender", kind: ParamType::Address, indexed: true },
+    EventParam { name: "_recipientAddr", kind: ParamType::Address, indexed: true },
+    EventParam { name: "_swapID", kind: ParamType::FixedBytes(32), indexed: true },
+    EventParam { name: "_randomNumberHash", kind: ParamType::FixedBytes(32), indexed: false },
+    EventParam { name: "_timestamp", kind: ParamType::Uint(64), indexed: false },
+    EventParam { name: "_expireHeight", kind: ParamType::Uint(256), indexed: false },
+    EventParam { name: "_outAmount", kind: ParamType::Uint(256), indexed: false },
+    EventParam { name: "_praAmount", kind: ParamType::Uint(256), indexed: false },
+    EventParam { name: "_receiverAddr", kind: ParamType::String, indexed: false },
+  ],
+};
+
+fn h256(hex: &str) -> H256 {
+  H256::from_slice(&hex::decode(hex).unwrap())
+}
+
+fn htlc_topics() -> Vec<H256> {
+  vec![
+    h256("5a0cc384a12a55445d4625db5d24f6a72177fd330644e2d4b3ea0ebd6f78c54d"),
+    h256("0000000000000000000000001111111111111111111111111111111111111111"),
+    h256("0000000000000000000000002222222222222222222222222222222222222222"),
+    h256("3333333333333333333333333333333333333333333333333333333333333333"),
+  ]
+}
+
+// 100 tokens with 18 decimals, receiver "0x0202..02"
+fn htlc_data() -> Vec<u8> {
+  hex::decode([
+    "abababababababababababababababababababababababababababababababab",
+    "000000000000000000000000000000000000000000000000000000005e0be100",
+    "00000000000000000000000000000000000000000000000000000000008bc7a4",
+    "0000000000000000000000000000000000000000000000056bc75e2d63100000",
+    "0000000000000000000000000000000000000000000000056bc75e2d63100000",
+    "00000000000000000000000000000000000000000000000000000000000000c0",
+    "0000000000000000000000000000000000000000000000000000000000000042",
+    "3078303230323032303230323032303230323032303230323032303230323032",
+    "3032303230323032303230323032303230323032303230323032303230323032",
+    "3032000000000000000000000000000000000000000000000000000000000000",
+  ].concat()).unwrap()
+}
+
+#[test]
+fn should_compute_topic_from_signature() {
+  assert_eq!(
+    HTLC.signature(),
+    b"HTLC(address,address,bytes32,bytes32,uint64,uint256,uint256,uint256,string)".to_vec()
+  );
+  assert_eq!(HTLC.topic(), htlc_topics()[0]);
+}
+
+#[test]
+fn should_decode_htlc_log() {
+  let tokens = HTLC.decode(&htlc_topics(), &htlc_data()).unwrap();
+  assert_eq!(tokens.len(), 9);
+
+  let mut receiver = b"0x".to_vec();
+  receiver.extend_from_slice(&[b'0', b'2'].repeat(32));
+  let amount = U256::from(100) * U256::exp10(18);
+  assert_eq!(tokens, vec![
+    Token::Address(H160::repeat_byte(0x11)),
+    Token::Address(H160::repeat_byte(0x22)),
+    Token::FixedBytes(vec![0x33; 32]),
+    Token::FixedBytes(vec![0xab; 32]),
+    Token::Uint(U256::from(1_577_836_800u64)),
+    Token::Uint(U256::from(9_160_612u64)),
+    Token::Uint(amount),
+    Token::Uint(amount),
+    Token::String(receiver.clone()),
+  ]);
+  assert_eq!(tokens[2].clone().into_h256(), Some(H256::repeat_byte(0x33)));
+  assert_eq!(tokens[8].clone().into_bytes(), Some(receiver));
+}
+
+#[test]
+fn should_reject_malformed_logs() {
+  let (topics, data) = (htlc_topics(), htlc_data());
+
+  let mut other = topics.clone();
+  other[0] = H256::repeat_byte(1);
+  assert_eq!(HTLC.decode(&other, &data), Err(Error::SignatureMismatch));
+  assert_eq!(HTLC.decode(&topics[..3], &data), Err(Error::TopicCount));
+  assert_eq!(HTLC.decode(&topics, &data[..data.len() - 32]), Err(Error::DataTooShort));
+
+  // address with dirty padding
+  let mut dirty = topics.clone();
+  dirty[1] = H256::repeat_byte(0x11);
+  assert_eq!(HTLC.decode(&dirty, &data), Err(Error::InvalidValue));
+
+  // a uint64 above 2^64
+  let mut overflow = data.clone();
+  overflow[32 + 23] = 1;
+  assert_eq!(HTLC.decode(&topics, &overflow), Err(Error::InvalidValue));
+
+  // string offset pointing past the data
+  let mut offset = data.clone();
+  offset[5 * 32 + 30] = 0x02;
+  offset[5 * 32 + 31] = 0x00;
+  assert_eq!(HTLC.decode(&topics, &offset), Err(Error::DataTooShort));
+}
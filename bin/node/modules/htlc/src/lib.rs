@@ -0,0 +1,211 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod tests;
+
+use codec::{Decode, Encode};
+use rstd::vec::Vec;
+use primitives::H256;
+use support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{Currency, ReservableCurrency, Get},
+};
+use sp_runtime::{DispatchError, traits::Zero};
+use system::ensure_signed;
+use runtime_io::hashing::{sha2_256, blake2_256};
+
+pub type BalanceOf<T, I = DefaultInstance> = <<T as Trait<I>>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type SwapOf<T, I = DefaultInstance> = Swap<<T as system::Trait>::AccountId, BalanceOf<T, I>, <T as system::Trait>::BlockNumber>;
+
+/// Each instance of the module swaps one asset, so PRA uses the default instance and
+/// any other asset exposed as a `ReservableCurrency` gets an instance of its own.
+pub trait Trait<I: Instance = DefaultInstance>: system::Trait {
+	type Event: From<Event<Self, I>> + Into<<Self as system::Trait>::Event>;
+	/// The asset being swapped, locked in the reserved balance of the sender.
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Fewest blocks a swap stays claimable.
+	type MinTimelock: Get<Self::BlockNumber>;
+	/// Most blocks a swap stays claimable.
+	type MaxTimelock: Get<Self::BlockNumber>;
+}
+
+/// Hash function turning the preimage into the hashlock.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HashFunction {
+	Sha256,
+	Blake2_256,
+}
+
+impl HashFunction {
+	pub fn hash(&self, preimage: &[u8]) -> H256 {
+		match self {
+			HashFunction::Sha256 => H256::from(sha2_256(preimage)),
+			HashFunction::Blake2_256 => H256::from(blake2_256(preimage)),
+		}
+	}
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum SwapState {
+	/// Claimable with the preimage until `expire_height`
+	Open,
+	/// Paid to the recipient
+	Claimed,
+	/// Returned to the sender
+	Refunded,
+}
+
+/// Funds reserved from `sender` until `recipient` claims them with the preimage.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Swap<AccountId, Balance, BlockNumber> {
+	pub sender: AccountId,
+	pub recipient: AccountId,
+	pub amount: Balance,
+	pub hasher: HashFunction,
+	pub expire_height: BlockNumber,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait<I>, I: Instance = DefaultInstance> as Htlc {
+		/// Open swaps by hashlock.
+		pub Swaps get(swaps): map H256 => Option<SwapOf<T, I>>;
+		/// State of every hashlock ever used, so none is used twice.
+		pub States get(states): map H256 => Option<SwapState>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T, I = DefaultInstance>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T, I>,
+	{
+		/// hashlock, sender, recipient, amount, expire height
+		Opened(H256, AccountId, AccountId, Balance, BlockNumber),
+		/// hashlock, recipient, amount, preimage
+		Claimed(H256, AccountId, Balance, Vec<u8>),
+		/// hashlock, sender, amount
+		Refunded(H256, AccountId, Balance),
+	}
+}
+
+decl_error! {
+	/// Error for the htlc module.
+	pub enum Error for Module<T: Trait<I>, I: Instance> {
+		/// Nothing would be locked
+		ZeroAmount,
+		/// The timelock is outside `MinTimelock..=MaxTimelock`
+		InvalidTimelock,
+		/// The hashlock has been used already
+		SwapExists,
+		/// No open swap has this hashlock or preimage
+		NoSwap,
+		/// The swap can't be claimed anymore
+		SwapExpired,
+		/// The swap can't be refunded yet
+		SwapNotExpired,
+		/// The reserve of the sender has been slashed below the swap amount
+		ReserveShortfall,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait<I>, I: Instance = DefaultInstance> for enum Call where origin: T::Origin {
+		type Error = Error<T, I>;
+
+		fn deposit_event() = default;
+
+		const MinTimelock: T::BlockNumber = T::MinTimelock::get();
+		const MaxTimelock: T::BlockNumber = T::MaxTimelock::get();
+
+		// reserve `amount` for `recipient`, claimable with the preimage of `hashlock` for `timelock` blocks
+		fn open(origin, hashlock: H256, hasher: HashFunction, timelock: T::BlockNumber, recipient: T::AccountId, amount: BalanceOf<T, I>) {
+			let sender = ensure_signed(origin)?;
+
+			Self::open_swap(sender, hashlock, hasher, timelock, recipient, amount)?;
+		}
+
+		// anyone knowing the preimage can release the swap to its recipient
+		fn claim(origin, preimage: Vec<u8>) {
+			let _ = ensure_signed(origin)?;
+
+			Self::claim_swap(&preimage)?;
+		}
+
+		// anyone can return an expired swap to its sender
+		fn refund(origin, hashlock: H256) {
+			let _ = ensure_signed(origin)?;
+
+			Self::refund_swap(hashlock)?;
+		}
+	}
+}
+
+impl<T: Trait<I>, I: Instance> Module<T, I> {
+	pub fn open_swap(
+		sender: T::AccountId,
+		hashlock: H256,
+		hasher: HashFunction,
+		timelock: T::BlockNumber,
+		recipient: T::AccountId,
+		amount: BalanceOf<T, I>,
+	) -> Result<(), DispatchError> {
+		ensure!(!amount.is_zero(), Error::<T, I>::ZeroAmount);
+		ensure!(timelock >= T::MinTimelock::get() && timelock <= T::MaxTimelock::get(), Error::<T, I>::InvalidTimelock);
+		ensure!(!<States<I>>::exists(hashlock), Error::<T, I>::SwapExists);
+
+		T::Currency::reserve(&sender, amount)?;
+
+		let expire_height = <system::Module<T>>::block_number() + timelock;
+		<Swaps<T, I>>::insert(hashlock, Swap {
+			sender: sender.clone(),
+			recipient: recipient.clone(),
+			amount,
+			hasher,
+			expire_height,
+		});
+		<States<I>>::insert(hashlock, SwapState::Open);
+
+		Self::deposit_event(RawEvent::Opened(hashlock, sender, recipient, amount, expire_height));
+		Ok(())
+	}
+
+	/// Pay the open swap `preimage` unlocks to its recipient, returning its hashlock and the swap.
+	pub fn claim_swap(preimage: &[u8]) -> Result<(H256, SwapOf<T, I>), DispatchError> {
+		let (hashlock, swap) = Self::find(preimage).ok_or(Error::<T, I>::NoSwap)?;
+		ensure!(<system::Module<T>>::block_number() < swap.expire_height, Error::<T, I>::SwapExpired);
+
+		// reserves are pooled per account, other modules may have slashed into the swap amount
+		ensure!(T::Currency::reserved_balance(&swap.sender) >= swap.amount, Error::<T, I>::ReserveShortfall);
+		let shortfall = T::Currency::repatriate_reserved(&swap.sender, &swap.recipient, swap.amount)?;
+		ensure!(shortfall.is_zero(), Error::<T, I>::ReserveShortfall);
+
+		<Swaps<T, I>>::remove(hashlock);
+		<States<I>>::insert(hashlock, SwapState::Claimed);
+
+		Self::deposit_event(RawEvent::Claimed(hashlock, swap.recipient.clone(), swap.amount, preimage.to_vec()));
+		Ok((hashlock, swap))
+	}
+
+	/// Return an expired swap to its sender.
+	pub fn refund_swap(hashlock: H256) -> Result<SwapOf<T, I>, DispatchError> {
+		let swap = Self::swaps(hashlock).ok_or(Error::<T, I>::NoSwap)?;
+		ensure!(<system::Module<T>>::block_number() >= swap.expire_height, Error::<T, I>::SwapNotExpired);
+
+		T::Currency::unreserve(&swap.sender, swap.amount);
+
+		<Swaps<T, I>>::remove(hashlock);
+		<States<I>>::insert(hashlock, SwapState::Refunded);
+
+		Self::deposit_event(RawEvent::Refunded(hashlock, swap.sender.clone(), swap.amount));
+		Ok(swap)
+	}
+
+	// the open swap whose hashlock is the preimage hashed with the swap's own hash function
+	fn find(preimage: &[u8]) -> Option<(H256, SwapOf<T, I>)> {
+		[HashFunction::Sha256, HashFunction::Blake2_256].iter()
+			.map(|hasher| hasher.hash(preimage))
+			.filter_map(|hashlock| Self::swaps(hashlock).map(|swap| (hashlock, swap)))
+			.find(|(hashlock, swap)| swap.hasher.hash(preimage) == *hashlock)
+	}
+}
@@ -0,0 +1,184 @@
+#![cfg(test)]
+
+use super::*;
+
+use support::{assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types};
+// The testing primitives are very useful for avoiding having to work with signatures
+// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+use sp_runtime::{
+  Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup},
+};
+
+impl_outer_origin! {
+  pub enum Origin for Test {}
+}
+
+mod htlc {
+  pub use super::super::*;
+}
+
+impl_outer_event! {
+  pub enum Event for Test {
+    htlc<T>, balances<T>,
+  }
+}
+// For testing the module, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of modules we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+  pub const BlockHashCount: u64 = 250;
+  pub const MaximumBlockWeight: u32 = 1024;
+  pub const MaximumBlockLength: u32 = 2 * 1024;
+  pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl system::Trait for Test {
+  type Origin = Origin;
+  type Index = u64;
+  type BlockNumber = u64;
+  type Hash = H256;
+  type Call = ();
+  type Hashing = BlakeTwo256;
+  type AccountId = u64;
+  type Lookup = IdentityLookup<Self::AccountId>;
+  type Header = Header;
+  type Event = Event;
+  type BlockHashCount = BlockHashCount;
+  type MaximumBlockWeight = MaximumBlockWeight;
+  type MaximumBlockLength = MaximumBlockLength;
+  type AvailableBlockRatio = AvailableBlockRatio;
+  type Version = ();
+}
+parameter_types! {
+  pub const ExistentialDeposit: u64 = 0;
+  pub const TransferFee: u64 = 0;
+  pub const CreationFee: u64 = 0;
+}
+impl balances::Trait for Test {
+  type Balance = u64;
+  type OnFreeBalanceZero = ();
+  type OnNewAccount = ();
+  type Event = Event;
+  type TransferPayment = ();
+  type DustRemoval = ();
+  type ExistentialDeposit = ExistentialDeposit;
+  type TransferFee = TransferFee;
+  type CreationFee = CreationFee;
+}
+
+parameter_types! {
+  pub const MinTimelock: u64 = 10;
+  pub const MaxTimelock: u64 = 100;
+}
+
+impl Trait for Test {
+  type Event = Event;
+  type Currency = Balances;
+  type MinTimelock = MinTimelock;
+  type MaxTimelock = MaxTimelock;
+}
+
+type HtlcModule = Module<Test>;
+type HtlcError = Error<Test, DefaultInstance>;
+type Balances = balances::Module<Test>;
+type System = system::Module<Test>;
+
+// This function basically just builds a genesis storage key/value store according to
+// our desired mockup.
+fn new_test_ext() -> runtime_io::TestExternalities {
+  let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+  balances::GenesisConfig::<Test> {
+    balances: vec![
+      (1, 1000),
+      (2, 1000),
+    ],
+    vesting: vec![],
+  }.assimilate_storage(&mut t).unwrap();
+
+  t.into()
+}
+
+#[test]
+fn should_claim_with_preimage() {
+  new_test_ext().execute_with(|| {
+    let preimage = b"secret".to_vec();
+    let hashlock = HashFunction::Sha256.hash(&preimage);
+
+    assert_noop!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 10, 2, 0), HtlcError::ZeroAmount);
+    assert_noop!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 5, 2, 100), HtlcError::InvalidTimelock);
+    assert_noop!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 101, 2, 100), HtlcError::InvalidTimelock);
+    assert!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 10, 2, 2000).is_err());
+
+    assert_ok!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 10, 2, 100));
+    assert_eq!(Balances::reserved_balance(&1), 100);
+    assert_eq!(HtlcModule::states(hashlock), Some(SwapState::Open));
+    assert_noop!(HtlcModule::open(Origin::signed(2), hashlock, HashFunction::Sha256, 10, 1, 100), HtlcError::SwapExists);
+
+    assert_noop!(HtlcModule::claim(Origin::signed(3), b"guess".to_vec()), HtlcError::NoSwap);
+    assert_noop!(HtlcModule::refund(Origin::signed(1), hashlock), HtlcError::SwapNotExpired);
+
+    // anyone with the preimage releases the funds to the recipient
+    assert_ok!(HtlcModule::claim(Origin::signed(3), preimage.clone()));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), 900);
+    assert_eq!(Balances::free_balance(&2), 1100);
+    assert_eq!(HtlcModule::swaps(hashlock), None);
+    assert_eq!(HtlcModule::states(hashlock), Some(SwapState::Claimed));
+    assert_noop!(HtlcModule::claim(Origin::signed(3), preimage), HtlcError::NoSwap);
+  });
+}
+
+#[test]
+fn should_refund_after_timelock() {
+  new_test_ext().execute_with(|| {
+    let preimage = b"secret".to_vec();
+    let hashlock = HashFunction::Blake2_256.hash(&preimage);
+    System::set_block_number(1);
+
+    assert_ok!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Blake2_256, 10, 2, 100));
+
+    System::set_block_number(11);
+    assert_noop!(HtlcModule::claim(Origin::signed(2), preimage.clone()), HtlcError::SwapExpired);
+
+    assert_ok!(HtlcModule::refund(Origin::signed(2), hashlock));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), 1000);
+    assert_eq!(HtlcModule::states(hashlock), Some(SwapState::Refunded));
+    assert_noop!(HtlcModule::refund(Origin::signed(2), hashlock), HtlcError::NoSwap);
+  });
+}
+
+#[test]
+fn should_match_the_hash_function_of_the_swap() {
+  new_test_ext().execute_with(|| {
+    let preimage = b"secret".to_vec();
+
+    // a sha256 hashlock opened as blake2-256 can't be claimed
+    let hashlock = HashFunction::Sha256.hash(&preimage);
+    assert_ok!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Blake2_256, 10, 2, 100));
+    assert_noop!(HtlcModule::claim(Origin::signed(2), preimage.clone()), HtlcError::NoSwap);
+
+    let hashlock = HashFunction::Blake2_256.hash(&preimage);
+    assert_ok!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Blake2_256, 10, 2, 100));
+    assert_ok!(HtlcModule::claim(Origin::signed(2), preimage));
+    assert_eq!(Balances::free_balance(&2), 1100);
+    assert_eq!(Balances::reserved_balance(&1), 100);
+  });
+}
+
+#[test]
+fn should_not_claim_a_slashed_reserve() {
+  new_test_ext().execute_with(|| {
+    let preimage = b"secret".to_vec();
+    let hashlock = HashFunction::Sha256.hash(&preimage);
+    assert_ok!(HtlcModule::open(Origin::signed(1), hashlock, HashFunction::Sha256, 10, 2, 100));
+
+    // another module slashing the pooled reserve leaves the swap open instead of paying it short
+    let _ = Balances::slash_reserved(&1, 40);
+    assert_noop!(HtlcModule::claim(Origin::signed(2), preimage), HtlcError::ReserveShortfall);
+    assert_eq!(HtlcModule::states(hashlock), Some(SwapState::Open));
+    assert_eq!(Balances::free_balance(&2), 1000);
+  });
+}
@@ -0,0 +1,352 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod tests;
+
+use codec::{Decode, Encode};
+use rstd::vec::Vec;
+use support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{Currency, ReservableCurrency, ExistenceRequirement, WithdrawReason, OnUnbalanced, Get},
+};
+use sp_runtime::traits::{CheckedAdd, CheckedMul, Saturating, SaturatedConversion};
+use system::ensure_signed;
+
+type NegativeImbalanceOf<T> = <balances::Module<T> as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Every registered name carries this suffix, e.g. `alice.pra`.
+pub const NAME_SUFFIX: &[u8] = b".pra";
+
+pub trait Trait: balances::Trait + did::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Rent charged for every `RentPeriod` a name is held.
+	type RentPerPeriod: Get<Self::Balance>;
+	/// The length of one rent period in blocks, roughly a year.
+	type RentPeriod: Get<Self::BlockNumber>;
+	/// Blocks after expiry during which only the previous owner can renew.
+	type GracePeriod: Get<Self::BlockNumber>;
+	/// Labels shorter than this can only be acquired through an auction.
+	type ShortNameLength: Get<usize>;
+	/// The minimum label length, not counting the suffix.
+	type MinLength: Get<usize>;
+	/// The maximum label length, not counting the suffix.
+	type MaxLength: Get<usize>;
+	/// How long an auction for a short name stays open after the first bid.
+	type AuctionPeriod: Get<Self::BlockNumber>;
+	/// The opening bid for a short name auction.
+	type MinBid: Get<Self::Balance>;
+	/// What to do with rent and winning bids.
+	type Payment: OnUnbalanced<NegativeImbalanceOf<Self>>;
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct NameRecord<Hash, BlockNumber> {
+	owner: Hash,
+	expires: BlockNumber,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct Auction<AccountId, Hash, Balance, BlockNumber> {
+	bidder: AccountId,
+	bidder_did: Hash,
+	amount: Balance,
+	ends: BlockNumber,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as NamesModule {
+		pub Registrations get(registrations): map Vec<u8> => Option<NameRecord<T::Hash, T::BlockNumber>>;
+		pub PrimaryName get(primary_name): map T::Hash => Option<Vec<u8>>;
+		pub Auctions get(auctions): map Vec<u8> => Option<Auction<T::AccountId, T::Hash, T::Balance, T::BlockNumber>>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T>
+	where
+		<T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber,
+		<T as balances::Trait>::Balance,
+	{
+		Registered(Hash, Vec<u8>, BlockNumber),
+		Renewed(Hash, Vec<u8>, BlockNumber),
+		PrimarySet(Hash, Vec<u8>),
+		BidPlaced(Hash, Vec<u8>, Balance),
+		AuctionSettled(Hash, Vec<u8>, Balance),
+	}
+}
+
+decl_error! {
+	/// Error for the names module.
+	pub enum Error for Module<T: Trait> {
+		/// The account has no did yet
+		NoDid,
+		/// The name does not end with `.pra`
+		InvalidSuffix,
+		/// The label is shorter than `MinLength`
+		TooShort,
+		/// The label is longer than `MaxLength`
+		TooLong,
+		/// The label contains something other than a-z, 0-9 or an inner hyphen
+		InvalidCharacter,
+		/// Short names can only be acquired by auction
+		ShortName,
+		/// Only short names are auctioned
+		NotShortName,
+		/// Registrations and renewals need at least one period
+		ZeroPeriods,
+		/// The name is registered and not past its grace period
+		NameTaken,
+		/// The name is not registered
+		NotRegistered,
+		/// The name is owned by another did
+		NotOwner,
+		/// The name is past its grace period
+		NameExpired,
+		/// The bid is below the minimum or the current bid
+		BidTooLow,
+		/// The auction is over, it can only be settled
+		AuctionEnded,
+		/// The auction is still accepting bids
+		AuctionRunning,
+		/// There is no auction for the name
+		NoAuction,
+		/// An arithmetic overflow happened
+		Overflow,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		const RentPerPeriod: T::Balance = T::RentPerPeriod::get();
+		const RentPeriod: T::BlockNumber = T::RentPeriod::get();
+		const GracePeriod: T::BlockNumber = T::GracePeriod::get();
+		const ShortNameLength: u32 = T::ShortNameLength::get() as u32;
+		const MinLength: u32 = T::MinLength::get() as u32;
+		const MaxLength: u32 = T::MaxLength::get() as u32;
+		const AuctionPeriod: T::BlockNumber = T::AuctionPeriod::get();
+		const MinBid: T::Balance = T::MinBid::get();
+
+		// register a name for `periods` rent periods, paid upfront
+		fn register(origin, name: Vec<u8>, periods: u32) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(&sender), Error::<T>::NoDid);
+			let did = <did::Module<T>>::identity(&sender);
+
+			let label_len = Self::validate(&name)?;
+			ensure!(label_len >= T::ShortNameLength::get(), Error::<T>::ShortName);
+			ensure!(periods > 0, Error::<T>::ZeroPeriods);
+			ensure!(Self::is_available(&name), Error::<T>::NameTaken);
+
+			let rent = T::RentPerPeriod::get()
+				.checked_mul(&Self::u32_to_balance(periods))
+				.ok_or(Error::<T>::Overflow)?;
+			let imbalance = <balances::Module<T> as Currency<_>>::withdraw(
+				&sender,
+				rent,
+				WithdrawReason::Fee.into(),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			T::Payment::on_unbalanced(imbalance);
+
+			let now = <system::Module<T>>::block_number();
+			let expires = Self::extend(now, periods)?;
+			Self::assign(&name, did, expires);
+
+			Self::deposit_event(RawEvent::Registered(did, name, expires));
+		}
+
+		// extend an owned name by `periods` rent periods, also possible during the grace period
+		fn renew(origin, name: Vec<u8>, periods: u32) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(&sender), Error::<T>::NoDid);
+			let did = <did::Module<T>>::identity(&sender);
+
+			let mut record = Self::registrations(&name).ok_or(Error::<T>::NotRegistered)?;
+			ensure!(record.owner == did, Error::<T>::NotOwner);
+			ensure!(periods > 0, Error::<T>::ZeroPeriods);
+
+			let now = <system::Module<T>>::block_number();
+			let grace_end = record.expires.checked_add(&T::GracePeriod::get()).ok_or(Error::<T>::Overflow)?;
+			ensure!(now <= grace_end, Error::<T>::NameExpired);
+
+			let rent = T::RentPerPeriod::get()
+				.checked_mul(&Self::u32_to_balance(periods))
+				.ok_or(Error::<T>::Overflow)?;
+			let imbalance = <balances::Module<T> as Currency<_>>::withdraw(
+				&sender,
+				rent,
+				WithdrawReason::Fee.into(),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			T::Payment::on_unbalanced(imbalance);
+
+			record.expires = Self::extend(record.expires, periods)?;
+			<Registrations<T>>::insert(&name, &record);
+
+			Self::deposit_event(RawEvent::Renewed(did, name, record.expires));
+		}
+
+		// choose which of the owned names the did resolves back to
+		fn set_primary(origin, name: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(&sender), Error::<T>::NoDid);
+			let did = <did::Module<T>>::identity(&sender);
+
+			ensure!(Self::resolve(&name) == Some(did), Error::<T>::NotOwner);
+
+			<PrimaryName<T>>::insert(did, &name);
+
+			Self::deposit_event(RawEvent::PrimarySet(did, name));
+		}
+
+		// bid on a short name, the bid stays reserved until outbid or settled
+		fn bid(origin, name: Vec<u8>, amount: T::Balance) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(<did::Identity<T>>::exists(&sender), Error::<T>::NoDid);
+			let did = <did::Module<T>>::identity(&sender);
+
+			let label_len = Self::validate(&name)?;
+			ensure!(label_len < T::ShortNameLength::get(), Error::<T>::NotShortName);
+			ensure!(Self::is_available(&name), Error::<T>::NameTaken);
+
+			let now = <system::Module<T>>::block_number();
+			let previous = Self::auctions(&name);
+			let ends = match &previous {
+				Some(auction) => {
+					ensure!(now < auction.ends, Error::<T>::AuctionEnded);
+					ensure!(amount > auction.amount, Error::<T>::BidTooLow);
+					auction.ends
+				},
+				None => {
+					ensure!(amount >= T::MinBid::get(), Error::<T>::BidTooLow);
+					now.checked_add(&T::AuctionPeriod::get()).ok_or(Error::<T>::Overflow)?
+				},
+			};
+
+			<balances::Module<T>>::reserve(&sender, amount)?;
+			if let Some(auction) = previous {
+				<balances::Module<T>>::unreserve(&auction.bidder, auction.amount);
+			}
+
+			<Auctions<T>>::insert(&name, Auction {
+				bidder: sender,
+				bidder_did: did,
+				amount,
+				ends,
+			});
+
+			Self::deposit_event(RawEvent::BidPlaced(did, name, amount));
+		}
+
+		// close an ended auction, the winning bid pays for the first rent period
+		fn settle_auction(origin, name: Vec<u8>) {
+			ensure_signed(origin)?;
+
+			let auction = Self::auctions(&name).ok_or(Error::<T>::NoAuction)?;
+			let now = <system::Module<T>>::block_number();
+			ensure!(now >= auction.ends, Error::<T>::AuctionRunning);
+
+			let (imbalance, _) = <balances::Module<T>>::slash_reserved(&auction.bidder, auction.amount);
+			T::Payment::on_unbalanced(imbalance);
+
+			<Auctions<T>>::remove(&name);
+
+			let expires = Self::extend(now, 1)?;
+			Self::assign(&name, auction.bidder_did, expires);
+
+			Self::deposit_event(RawEvent::AuctionSettled(auction.bidder_did, name, auction.amount));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The DID owning `name`, provided the registration has not expired.
+	pub fn resolve(name: &[u8]) -> Option<T::Hash> {
+		let record = Self::registrations(name.to_vec())?;
+		if <system::Module<T>>::block_number() <= record.expires {
+			Some(record.owner)
+		} else {
+			None
+		}
+	}
+
+	/// The primary name of `did`, provided it still resolves back to `did`.
+	pub fn name_of(did: T::Hash) -> Option<Vec<u8>> {
+		let name = Self::primary_name(did)?;
+		if Self::resolve(&name) == Some(did) {
+			Some(name)
+		} else {
+			None
+		}
+	}
+
+	// a name can be taken when it was never registered or its grace period is over
+	fn is_available(name: &[u8]) -> bool {
+		match Self::registrations(name.to_vec()) {
+			None => true,
+			Some(record) => {
+				let grace_end = record.expires.saturating_add(T::GracePeriod::get());
+				<system::Module<T>>::block_number() > grace_end
+			},
+		}
+	}
+
+	// checks the suffix and charset, returns the label length
+	fn validate(name: &[u8]) -> rstd::result::Result<usize, Error<T>> {
+		ensure!(name.ends_with(NAME_SUFFIX), Error::<T>::InvalidSuffix);
+
+		let label = &name[..name.len() - NAME_SUFFIX.len()];
+		// an empty label is too short whatever `MinLength` says
+		ensure!(!label.is_empty() && label.len() >= T::MinLength::get(), Error::<T>::TooShort);
+		ensure!(label.len() <= T::MaxLength::get(), Error::<T>::TooLong);
+		ensure!(
+			label.iter().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == b'-'),
+			Error::<T>::InvalidCharacter
+		);
+		ensure!(label.first() != Some(&b'-') && label.last() != Some(&b'-'), Error::<T>::InvalidCharacter);
+
+		Ok(label.len())
+	}
+
+	fn extend(from: T::BlockNumber, periods: u32) -> rstd::result::Result<T::BlockNumber, Error<T>> {
+		let duration = T::RentPeriod::get()
+			.checked_mul(&T::BlockNumber::from(periods))
+			.ok_or(Error::<T>::Overflow)?;
+		from.checked_add(&duration).ok_or(Error::<T>::Overflow)
+	}
+
+	fn assign(name: &Vec<u8>, did: T::Hash, expires: T::BlockNumber) {
+		// the previous owner must not resolve back to a name it lost
+		if let Some(previous) = Self::registrations(name) {
+			if Self::primary_name(previous.owner).as_ref() == Some(name) {
+				<PrimaryName<T>>::remove(previous.owner);
+			}
+		}
+
+		<Registrations<T>>::insert(name, NameRecord { owner: did, expires });
+
+		if Self::name_of(did).is_none() {
+			<PrimaryName<T>>::insert(did, name);
+		}
+	}
+
+	fn u32_to_balance(input: u32) -> T::Balance {
+		input.saturated_into()
+	}
+}
+
+impl<T: Trait> did::NameResolver<T::Hash> for Module<T> {
+	fn resolve(name: &[u8]) -> Option<T::Hash> {
+		Self::resolve(name)
+	}
+}
@@ -0,0 +1,323 @@
+#![cfg(test)]
+
+use super::*;
+
+use support::{assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types};
+use primitives::H256;
+// The testing primitives are very useful for avoiding having to work with signatures
+// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+use sp_runtime::{
+  Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup},
+};
+use did::DidOrName;
+
+impl_outer_origin! {
+  pub enum Origin for Test {}
+}
+
+mod names {
+  pub use super::super::*;
+}
+
+impl_outer_event! {
+  pub enum Event for Test {
+    did<T>, names<T>, balances<T>,
+  }
+}
+// For testing the module, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of modules we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+  pub const BlockHashCount: u64 = 250;
+  pub const MaximumBlockWeight: u32 = 1024;
+  pub const MaximumBlockLength: u32 = 2 * 1024;
+  pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl system::Trait for Test {
+  type Origin = Origin;
+  type Index = u64;
+  type BlockNumber = u64;
+  type Hash = H256;
+  type Call = ();
+  type Hashing = BlakeTwo256;
+  type AccountId = u64;
+  type Lookup = IdentityLookup<Self::AccountId>;
+  type Header = Header;
+  type Event = Event;
+  type BlockHashCount = BlockHashCount;
+  type MaximumBlockWeight = MaximumBlockWeight;
+  type MaximumBlockLength = MaximumBlockLength;
+  type AvailableBlockRatio = AvailableBlockRatio;
+  type Version = ();
+}
+parameter_types! {
+  pub const ExistentialDeposit: u64 = 0;
+  pub const TransferFee: u64 = 0;
+  pub const CreationFee: u64 = 0;
+}
+impl balances::Trait for Test {
+  type Balance = u64;
+  type OnFreeBalanceZero = ();
+  type OnNewAccount = ();
+  type Event = Event;
+  type TransferPayment = ();
+  type DustRemoval = ();
+  type ExistentialDeposit = ExistentialDeposit;
+  type TransferFee = TransferFee;
+  type CreationFee = CreationFee;
+}
+
+parameter_types! {
+  pub const MinimumPeriod: u64 = 1;
+}
+
+impl timestamp::Trait for Test {
+  type Moment = u64;
+  type OnTimestampSet = ();
+  type MinimumPeriod = MinimumPeriod;
+}
+
+impl did::Trait for Test {
+  type Event = Event;
+  type NameResolver = NamesModule;
+}
+
+parameter_types! {
+  pub const RentPerPeriod: u64 = 10;
+  pub const RentPeriod: u64 = 100;
+  pub const GracePeriod: u64 = 10;
+  pub const ShortNameLength: usize = 4;
+  pub const MinLength: usize = 2;
+  pub const MaxLength: usize = 16;
+  pub const AuctionPeriod: u64 = 5;
+  pub const MinBid: u64 = 100;
+}
+
+impl Trait for Test {
+  type Event = Event;
+  type RentPerPeriod = RentPerPeriod;
+  type RentPeriod = RentPeriod;
+  type GracePeriod = GracePeriod;
+  type ShortNameLength = ShortNameLength;
+  type MinLength = MinLength;
+  type MaxLength = MaxLength;
+  type AuctionPeriod = AuctionPeriod;
+  type MinBid = MinBid;
+  type Payment = ();
+}
+
+type NamesModule = Module<Test>;
+type System = system::Module<Test>;
+type Balances = balances::Module<Test>;
+type DidModule = did::Module<Test>;
+
+// This function basically just builds a genesis storage key/value store according to
+// our desired mockup.
+fn new_test_ext() -> runtime_io::TestExternalities {
+  let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+  // We use default for brevity, but you can configure as desired if needed.
+  balances::GenesisConfig::<Test> {
+    balances: vec![
+      (1, 10000),
+      (2, 10000),
+      (3, 10000),
+    ],
+    vesting: vec![],
+  }.assimilate_storage(&mut t).unwrap();
+
+  did::GenesisConfig::<Test> {
+    genesis_account: 1u64,
+    min_deposit: 50,
+    base_quota: 250,
+    fee_to_previous: 25,
+  }.assimilate_storage(&mut t).unwrap();
+
+  t.into()
+}
+
+fn prepare_dids_for_test() {
+  // genesis account
+  assert_ok!(DidModule::create(
+    Origin::signed(1),
+    b"0x22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f".to_vec(),
+    1u64,
+    "1".as_bytes().to_vec(),
+    H256::zero(),
+    Some("f".as_bytes().to_vec()),
+    None
+  ));
+
+  // first account
+  assert_ok!(DidModule::create(
+    Origin::signed(1),
+    b"0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d".to_vec(),
+    2u64,
+    "1".as_bytes().to_vec(),
+    H256::zero(),
+    Some("s".as_bytes().to_vec()),
+    Some("f".as_bytes().to_vec())
+  ));
+
+  // second account
+  assert_ok!(DidModule::create(
+    Origin::signed(1),
+    b"0x5e9c79234b5e55348fc60f38b28c2cc60d8bb4bd2862eae2179a05ec39e62658".to_vec(),
+    3u64,
+    "1".as_bytes().to_vec(),
+    H256::zero(),
+    Some("n".as_bytes().to_vec()),
+    Some("f".as_bytes().to_vec())
+  ));
+}
+
+#[test]
+fn should_pass_register() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+    prepare_dids_for_test();
+
+    assert_ok!(NamesModule::register(Origin::signed(2), b"alice.pra".to_vec(), 2));
+    assert_eq!(Balances::free_balance(&2), 9980);
+
+    let did = DidModule::identity(2);
+    assert_eq!(NamesModule::resolve(b"alice.pra"), Some(did));
+    assert_eq!(NamesModule::name_of(did), Some(b"alice.pra".to_vec()));
+    assert_eq!(NamesModule::registrations(b"alice.pra".to_vec()), Some(NameRecord {
+      owner: did,
+      expires: 201,
+    }));
+
+    assert_noop!(
+      NamesModule::register(Origin::signed(3), b"alice.pra".to_vec(), 1),
+      Error::<Test>::NameTaken
+    );
+  });
+}
+
+#[test]
+fn invalid_names_should_not_pass() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_noop!(NamesModule::register(Origin::signed(2), b"alice".to_vec(), 1), Error::<Test>::InvalidSuffix);
+    assert_noop!(NamesModule::register(Origin::signed(2), b"Alice.pra".to_vec(), 1), Error::<Test>::InvalidCharacter);
+    assert_noop!(NamesModule::register(Origin::signed(2), b"-alice.pra".to_vec(), 1), Error::<Test>::InvalidCharacter);
+    assert_noop!(NamesModule::register(Origin::signed(2), b"bob.pra".to_vec(), 1), Error::<Test>::ShortName);
+    assert_noop!(NamesModule::register(Origin::signed(4), b"alice.pra".to_vec(), 1), Error::<Test>::NoDid);
+  });
+}
+
+#[test]
+fn should_expire_and_renew() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+    prepare_dids_for_test();
+
+    assert_ok!(NamesModule::register(Origin::signed(2), b"alice.pra".to_vec(), 1));
+    let did = DidModule::identity(2);
+
+    // expired, but still inside the grace period
+    System::set_block_number(105);
+    assert_eq!(NamesModule::resolve(b"alice.pra"), None);
+    assert_eq!(NamesModule::name_of(did), None);
+    assert_noop!(
+      NamesModule::register(Origin::signed(3), b"alice.pra".to_vec(), 1),
+      Error::<Test>::NameTaken
+    );
+
+    assert_ok!(NamesModule::renew(Origin::signed(2), b"alice.pra".to_vec(), 1));
+    assert_eq!(NamesModule::resolve(b"alice.pra"), Some(did));
+    assert_eq!(Balances::free_balance(&2), 9980);
+
+    // past the grace period anybody can take it
+    System::set_block_number(212);
+    assert_noop!(
+      NamesModule::renew(Origin::signed(2), b"alice.pra".to_vec(), 1),
+      Error::<Test>::NameExpired
+    );
+    assert_ok!(NamesModule::register(Origin::signed(3), b"alice.pra".to_vec(), 1));
+    assert_eq!(NamesModule::resolve(b"alice.pra"), Some(DidModule::identity(3)));
+    assert_eq!(NamesModule::primary_name(did), None);
+  });
+}
+
+#[test]
+fn should_pass_set_primary() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+    prepare_dids_for_test();
+
+    assert_ok!(NamesModule::register(Origin::signed(2), b"alice.pra".to_vec(), 1));
+    assert_ok!(NamesModule::register(Origin::signed(2), b"alice-shop.pra".to_vec(), 1));
+
+    let did = DidModule::identity(2);
+    assert_eq!(NamesModule::name_of(did), Some(b"alice.pra".to_vec()));
+
+    assert_ok!(NamesModule::set_primary(Origin::signed(2), b"alice-shop.pra".to_vec()));
+    assert_eq!(NamesModule::name_of(did), Some(b"alice-shop.pra".to_vec()));
+
+    assert_noop!(
+      NamesModule::set_primary(Origin::signed(3), b"alice.pra".to_vec()),
+      Error::<Test>::NotOwner
+    );
+  });
+}
+
+#[test]
+fn should_auction_short_names() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+    prepare_dids_for_test();
+
+    assert_noop!(NamesModule::bid(Origin::signed(2), b"bob.pra".to_vec(), 50), Error::<Test>::BidTooLow);
+    assert_ok!(NamesModule::bid(Origin::signed(2), b"bob.pra".to_vec(), 100));
+    assert_eq!(Balances::reserved_balance(&2), 100);
+
+    System::set_block_number(3);
+    assert_noop!(
+      NamesModule::bid(Origin::signed(3), b"bob.pra".to_vec(), 100),
+      Error::<Test>::BidTooLow
+    );
+    assert_ok!(NamesModule::bid(Origin::signed(3), b"bob.pra".to_vec(), 150));
+    assert_eq!(Balances::reserved_balance(&2), 0);
+    assert_eq!(Balances::reserved_balance(&3), 150);
+
+    assert_noop!(NamesModule::settle_auction(Origin::signed(1), b"bob.pra".to_vec()), Error::<Test>::AuctionRunning);
+
+    System::set_block_number(6);
+    assert_noop!(NamesModule::bid(Origin::signed(2), b"bob.pra".to_vec(), 200), Error::<Test>::AuctionEnded);
+    assert_ok!(NamesModule::settle_auction(Origin::signed(1), b"bob.pra".to_vec()));
+
+    assert_eq!(Balances::reserved_balance(&3), 0);
+    assert_eq!(Balances::free_balance(&3), 9850);
+    assert_eq!(NamesModule::resolve(b"bob.pra"), Some(DidModule::identity(3)));
+    assert_eq!(NamesModule::auctions(b"bob.pra".to_vec()), None);
+  });
+}
+
+#[test]
+fn should_transfer_by_name() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+    prepare_dids_for_test();
+
+    assert_ok!(NamesModule::register(Origin::signed(3), b"carol.pra".to_vec(), 1));
+    assert_ok!(DidModule::transfer(
+      Origin::signed(2),
+      DidOrName::Name(b"carol.pra".to_vec()),
+      100,
+      b"transfer by name".to_vec()
+    ));
+    assert_eq!(Balances::free_balance(&3), 10090);
+
+    assert_noop!(DidModule::transfer(
+      Origin::signed(2),
+      DidOrName::Name(b"nobody.pra".to_vec()),
+      100,
+      b"transfer by name".to_vec()
+    ), did::Error::<Test>::NameNotResolved);
+  });
+}
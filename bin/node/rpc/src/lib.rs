@@ -31,7 +31,7 @@
 
 use std::sync::Arc;
 
-use node_primitives::{Block, AccountId, Index, Balance};
+use node_primitives::{Block, AccountId, Index, Balance, Hash, Moment};
 use node_runtime::UncheckedExtrinsic;
 use sp_runtime::traits::ProvideRuntimeApi;
 use txpool_api::TransactionPool;
@@ -68,6 +68,7 @@ pub fn create<C, P, M, F>(
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: ads_rpc::AdsRuntimeApi<Block, Hash, Balance, Moment>,
 	F: client::light::fetcher::Fetcher<Block> + 'static,
 	P: TransactionPool + 'static,
 	M: jsonrpc_core::Metadata + Default,
@@ -75,6 +76,7 @@ pub fn create<C, P, M, F>(
 	use substrate_frame_rpc_system::{FullSystem, LightSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use ads_rpc::{Ads, AdsApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	
@@ -93,6 +95,9 @@ pub fn create<C, P, M, F>(
 		io.extend_with(
 			ContractsApi::to_delegate(Contracts::new(client.clone()))
 		);
+		io.extend_with(
+			AdsApi::to_delegate(Ads::new(client.clone()))
+		);
 		io.extend_with(
 			TransactionPaymentApi::to_delegate(TransactionPayment::new(client))
 		);
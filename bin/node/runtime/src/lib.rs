@@ -80,8 +80,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 198,
-	impl_version: 198,
+	spec_version: 223,
+	impl_version: 223,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -511,21 +511,107 @@ impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtim
 
 impl did::Trait for Runtime {
 	type Event = Event;
+	type NameResolver = Names;
 }
 
 use oracle::sr25519::AuthorityId as OracleId;
 // We need to define the Transaction signer for that using the Key definition
 type SubmitTransactionOracle = TransactionSubmitter<OracleId, Runtime, UncheckedExtrinsic>;
 
+parameter_types! {
+	// ProToken is deployed with 8 decimals, see eth-erc20-swap/eth-contracts/migrations
+	pub const PraTokenDecimals: u8 = 8;
+	// DOLLARS = 10^15
+	pub const NativeDecimals: u8 = 15;
+	pub const EthConfirmationDepth: u64 = 12;
+	pub const EthFinalityDepth: u64 = 100;
+	pub const OracleSwapRetention: BlockNumber = 30 * DAYS;
+	pub const MaxOracleSweepsPerBlock: u32 = 10;
+}
+
 impl oracle::Trait for Runtime {
 	type Call = Call;
 	type Event = Event;
 	type AuthorityId = OracleId;
 	type SubmitTransaction = SubmitTransactionOracle;
+	type TokenDecimals = PraTokenDecimals;
+	type NativeDecimals = NativeDecimals;
+	type ConfirmationDepth = EthConfirmationDepth;
+	type FinalityDepth = EthFinalityDepth;
+	type SwapRetention = OracleSwapRetention;
+	type MaxSweepsPerBlock = MaxOracleSweepsPerBlock;
+}
+
+parameter_types! {
+	pub const HtlcMinTimelock: BlockNumber = 100;
+	pub const HtlcMaxTimelock: BlockNumber = 7 * DAYS;
+}
+
+impl htlc::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type MinTimelock = HtlcMinTimelock;
+	type MaxTimelock = HtlcMaxTimelock;
+}
+
+parameter_types! {
+	pub const MaxAdsExpiriesPerBlock: u32 = 10;
+	pub const AdsCloseReward: Balance = 1 * DOLLARS;
+	pub const MaxReceiptsPerSettle: u32 = 500;
+	pub const AdsClaimPeriod: Moment = 30 * DAYS as Moment * MILLISECS_PER_BLOCK;
+	pub const MaxAdsTags: u32 = 10;
+	pub const MaxAdsTagLength: u32 = 32;
+	pub const AdsDayLength: Moment = DAYS as Moment * MILLISECS_PER_BLOCK;
+	pub const AdsStatsHistoryDays: u32 = 30;
+	pub const AdsRefundOnSuspend: bool = false;
+	pub const AdsReportBond: Balance = 10 * DOLLARS;
+	pub const AdsReporterReward: Perbill = Perbill::from_percent(10);
 }
 
 impl ads::Trait for Runtime {
 	type Event = Event;
+	type MaxExpiriesPerBlock = MaxAdsExpiriesPerBlock;
+	type CloseReward = AdsCloseReward;
+	type Signature = Signature;
+	type Public = <Signature as traits::Verify>::Signer;
+	type MaxReceiptsPerSettle = MaxReceiptsPerSettle;
+	type ClaimPeriod = AdsClaimPeriod;
+	type MaxTags = MaxAdsTags;
+	type MaxTagLength = MaxAdsTagLength;
+	type DayLength = AdsDayLength;
+	type StatsHistoryDays = AdsStatsHistoryDays;
+	type PlatformFeeOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	type PlatformFeeDestination = Treasury;
+	type ModerationOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	type RefundOnSuspend = AdsRefundOnSuspend;
+	type ReportBond = AdsReportBond;
+	type ReporterReward = AdsReporterReward;
+	type ReportOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	type Slash = Treasury;
+}
+
+parameter_types! {
+	pub const NameRentPerPeriod: Balance = 10 * DOLLARS;
+	pub const NameRentPeriod: BlockNumber = 365 * DAYS;
+	pub const NameGracePeriod: BlockNumber = 30 * DAYS;
+	pub const ShortNameLength: usize = 5;
+	pub const NameMinLength: usize = 1;
+	pub const NameMaxLength: usize = 32;
+	pub const NameAuctionPeriod: BlockNumber = 7 * DAYS;
+	pub const NameMinBid: Balance = 100 * DOLLARS;
+}
+
+impl names::Trait for Runtime {
+	type Event = Event;
+	type RentPerPeriod = NameRentPerPeriod;
+	type RentPeriod = NameRentPeriod;
+	type GracePeriod = NameGracePeriod;
+	type ShortNameLength = ShortNameLength;
+	type MinLength = NameMinLength;
+	type MaxLength = NameMaxLength;
+	type AuctionPeriod = NameAuctionPeriod;
+	type MinBid = NameMinBid;
+	type Payment = Treasury;
 }
 
 construct_runtime!(
@@ -559,9 +645,11 @@ construct_runtime!(
 		Offences: offences::{Module, Call, Storage, Event},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
 		Nicks: nicks::{Module, Call, Storage, Event<T>},
-		Did: did::{Module, Storage, Call, Config<T>, Event<T>},
-		Oracle: oracle::{Module, Storage, Call, Event<T>, ValidateUnsigned},
-		Ads: ads::{Module, Storage, Call, Config<T>, Event<T>},
+		Did: did::{Module, Storage, Call, Config<T>, Event<T>, Error},
+		Oracle: oracle::{Module, Storage, Call, Config<T>, Event<T>, ValidateUnsigned, Error},
+		Ads: ads::{Module, Storage, Call, Config<T>, Event<T>, Error},
+		Names: names::{Module, Storage, Call, Event<T>, Error},
+		Htlc: htlc::{Module, Storage, Call, Event<T>, Error},
 	}
 );
 
@@ -735,6 +823,16 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl ads_rpc_runtime_api::AdsApi<Block, Hash, Balance, Moment> for Runtime {
+		fn active_campaigns(tags: Vec<Vec<u8>>) -> Vec<ads_rpc_runtime_api::CampaignInfo<Hash, Balance>> {
+			Ads::active_campaigns(tags)
+		}
+
+		fn campaign_report(campaign_id: Hash) -> Option<ads_rpc_runtime_api::CampaignReport<Balance, Moment>> {
+			Ads::campaign_report(campaign_id)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
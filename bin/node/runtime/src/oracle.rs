@@ -2,19 +2,21 @@
 
 use sp_runtime::app_crypto::{KeyTypeId, RuntimeAppPublic};
 use codec::{Decode, Encode};
-use primitives::{offchain::Duration, offchain::HttpRequestId, offchain::HttpRequestStatus};
+use primitives::{H160, H256, U256, offchain::Duration, offchain::HttpRequestId, offchain::HttpRequestStatus, offchain::StorageKind, offchain::Timestamp};
 use rstd::{prelude::*, result::Result, vec::Vec};
 use sp_runtime::{
-	traits::Member, traits::Hash,
+	traits::Member, traits::Hash, traits::Zero, traits::One,
 	transaction_validity::{
-		TransactionValidity, TransactionPriority, ValidTransaction, UnknownTransaction, TransactionLongevity}
+		TransactionValidity, TransactionPriority, ValidTransaction, InvalidTransaction, UnknownTransaction}
 };
-use support::{decl_event, decl_module, decl_storage, ensure, Parameter, StorageMap, StorageValue,
-	dispatch::Result as dispatch_result, weights::SimpleDispatchInfo};
+use support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter, StorageMap, StorageValue,
+	dispatch::DispatchResult, weights::SimpleDispatchInfo, traits::{Currency, ExistenceRequirement, Get}};
 use system::{offchain::SubmitUnsignedTransaction, ensure_none, ensure_signed, ensure_root};
 use simple_json::{self, json::JsonValue};
 use hex::FromHex;
-use core::convert::{TryInto};
+use core::{cmp, convert::{TryFrom, TryInto}};
+use eth_abi::{Event as AbiEvent, EventParam, ParamType, Token};
+use htlc::{HashFunction, SwapState};
 
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orin");
 
@@ -37,6 +39,16 @@ pub mod sr25519 {
 pub struct EventLogSource {
 	event_name: Vec<u8>,
 	event_url: Vec<u8>,
+	kind: LogSourceKind,
+}
+
+/// How `EventLogSource::event_url` serves ethereum logs.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum LogSourceKind {
+	/// Etherscan `getLogs` api, the url carries the contract address and api key
+	Etherscan,
+	/// Ethereum node answering `eth_blockNumber` and `eth_getLogs`, for the given 0x prefixed contract address
+	JsonRpc(Vec<u8>),
 }
 
 // Config event json parse fields
@@ -47,18 +59,200 @@ const KEY_ADDRESS: &'static str = "address";
 const KEY_TOPICS: &'static str = "topics";
 const KEY_DATA: &'static str = "data";
 const KEY_BLOCK_NUMBER: &'static str = "blockNumber";
+const KEY_BLOCK_HASH: &'static str = "blockHash";
+const KEY_HASH: &'static str = "hash";
 const KEY_TIME_STAMP: &'static str = "timeStamp";
 const KEY_TX_HASH: &'static str = "transactionHash";
 const KEY_TX_INDEX: &'static str = "transactionIndex";
 
+// offchain local storage keys
+const DB_FETCH_LOCK: &[u8] = b"oracle::fetch-lock";
+const DB_CURSOR_PREFIX: &[u8] = b"oracle::cursor::";
+
+// a crashed fetch releases the lock after this long
+const FETCH_LOCK_MILLIS: u64 = 60_000;
+// etherscan returns at most this many logs per request
+const MAX_LOGS: usize = 1000;
+
 const STATUS_OK: &'static str = "1";
 const MESSAGE_OK: &'static str = "OK";
+const STATUS_NOTOK: &'static str = "0";
+const MESSAGE_NO_RECORDS: &'static str = "No records found";
 const STR_PREFIX: &'static str = "0x";
 
-// TODO: auto generate EventSignature by contract abi
-const EVENT_SIG_HTLC: &'static str = "0x5a0cc384a12a55445d4625db5d24f6a72177fd330644e2d4b3ea0ebd6f78c54d";
-const EVENT_SIG_CLAIM: &'static str = "0x07a9dd1ef03da239626dc5c5bac1995991043d2b6e0e23ca789bbc0a16eb911f";
-const EVENT_SIG_REFUND: &'static str = "0x215e15eef6d0300f9e89d940198e4f7fc22e44b7c80118c03571cd96da6c6c98";
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+// Events of eth-erc20-swap/eth-contracts/contracts/ERC20HTLC.sol
+const HTLC_EVENT: AbiEvent = AbiEvent {
+	name: "HTLC",
+	inputs: &[
+		EventParam { name: "_msgSender", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_recipientAddr", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_swapID", kind: ParamType::FixedBytes(32), indexed: true },
+		EventParam { name: "_randomNumberHash", kind: ParamType::FixedBytes(32), indexed: false },
+		EventParam { name: "_timestamp", kind: ParamType::Uint(64), indexed: false },
+		EventParam { name: "_expireHeight", kind: ParamType::Uint(256), indexed: false },
+		EventParam { name: "_outAmount", kind: ParamType::Uint(256), indexed: false },
+		EventParam { name: "_praAmount", kind: ParamType::Uint(256), indexed: false },
+		EventParam { name: "_receiverAddr", kind: ParamType::String, indexed: false },
+	],
+};
+
+const CLAIMED_EVENT: AbiEvent = AbiEvent {
+	name: "Claimed",
+	inputs: &[
+		EventParam { name: "_msgSender", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_recipientAddr", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_swapID", kind: ParamType::FixedBytes(32), indexed: true },
+		EventParam { name: "_randomNumber", kind: ParamType::FixedBytes(32), indexed: false },
+		EventParam { name: "_receiverAddr", kind: ParamType::String, indexed: false },
+	],
+};
+
+const REFUNDED_EVENT: AbiEvent = AbiEvent {
+	name: "Refunded",
+	inputs: &[
+		EventParam { name: "_msgSender", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_recipientAddr", kind: ParamType::Address, indexed: true },
+		EventParam { name: "_swapID", kind: ParamType::FixedBytes(32), indexed: true },
+		EventParam { name: "_randomNumberHash", kind: ParamType::FixedBytes(32), indexed: false },
+		EventParam { name: "_receiverAddr", kind: ParamType::String, indexed: false },
+	],
+};
+
+/// An ethereum log as the sources return it, all fields 0x prefixed hex.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+struct RawLog {
+	address: Vec<u8>,
+	topics: Vec<Vec<u8>>,
+	data: Vec<u8>,
+	block_number: Vec<u8>,
+	/// only json rpc logs carry the block hash, empty otherwise
+	block_hash: Vec<u8>,
+	/// only etherscan knows the block time, `0x0` otherwise
+	time_stamp: Vec<u8>,
+	tx_hash: Vec<u8>,
+	tx_index: Vec<u8>,
+}
+
+/// Reads ethereum blocks and the logs of the htlc contract.
+trait LogSource<T: Trait> {
+	/// The latest block number.
+	fn head(&self, url: &str) -> Result<u64, OffchainErr>;
+	/// The hash of block `number`.
+	fn block_hash(&self, url: &str, number: u64) -> Result<H256, OffchainErr>;
+	/// The logs of blocks `from_block..=to_block`.
+	fn fetch_logs(&self, url: &str, from_block: u64, to_block: u64) -> Result<Vec<RawLog>, OffchainErr>;
+}
+
+struct Etherscan;
+
+impl Etherscan {
+	// the configured url calls `module=logs&action=getLogs`, the proxy calls go to the same api with the same key
+	fn proxy_url<T: Trait>(url: &str, action: &[u8]) -> Result<Vec<u8>, OffchainErr> {
+		const GET_LOGS: &[u8] = b"module=logs&action=getLogs";
+		let url = url.as_bytes();
+		let at = url.windows(GET_LOGS.len()).position(|w| w == GET_LOGS).ok_or(OffchainErr::InvalidUrl)?;
+
+		let mut proxy = url[..at].to_vec();
+		proxy.extend_from_slice(b"module=proxy&action=");
+		proxy.extend_from_slice(action);
+		proxy.extend_from_slice(&url[at + GET_LOGS.len()..]);
+		Ok(proxy)
+	}
+
+	fn get<T: Trait>(uri: &[u8]) -> Result<JsonValue, OffchainErr> {
+		let uri = core::str::from_utf8(uri).map_err(|_| OffchainErr::InvalidUrl)?;
+		let body = <Module<T>>::http_request_get(uri, None)?;
+		<Module<T>>::parse_json(&body)
+	}
+}
+
+impl<T: Trait> LogSource<T> for Etherscan {
+	fn head(&self, url: &str) -> Result<u64, OffchainErr> {
+		let response = Self::get::<T>(&Self::proxy_url::<T>(url, b"eth_blockNumber")?)?;
+		<Module<T>>::result_number(&response)
+	}
+
+	fn block_hash(&self, url: &str, number: u64) -> Result<H256, OffchainErr> {
+		let mut uri = Self::proxy_url::<T>(url, b"eth_getBlockByNumber")?;
+		uri.extend_from_slice(b"&tag=0x");
+		uri.extend_from_slice(&<Module<T>>::digits(number, 16));
+		uri.extend_from_slice(b"&boolean=false");
+		let response = Self::get::<T>(&uri)?;
+		<Module<T>>::result_block_hash(&response)
+	}
+
+	fn fetch_logs(&self, url: &str, from_block: u64, to_block: u64) -> Result<Vec<RawLog>, OffchainErr> {
+		let mut uri = url.as_bytes().to_vec();
+		uri.extend_from_slice(b"&fromBlock=");
+		uri.extend_from_slice(&<Module<T>>::digits(from_block, 10));
+		uri.extend_from_slice(b"&toBlock=");
+		uri.extend_from_slice(&<Module<T>>::digits(to_block, 10));
+		let response = Self::get::<T>(&uri)?;
+		<Module<T>>::etherscan_logs(&response)
+	}
+}
+
+struct JsonRpc {
+	contract_addr: Vec<u8>,
+}
+
+impl JsonRpc {
+	fn request(method: &[u8], params: &[u8]) -> Vec<u8> {
+		let mut body = b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"".to_vec();
+		body.extend_from_slice(method);
+		body.extend_from_slice(b"\",\"params\":[");
+		body.extend_from_slice(params);
+		body.extend_from_slice(b"]}");
+		body
+	}
+
+	fn post<T: Trait>(url: &str, method: &[u8], params: &[u8]) -> Result<JsonValue, OffchainErr> {
+		let body = <Module<T>>::http_request_post(url, &Self::request(method, params))?;
+		<Module<T>>::parse_json(&body)
+	}
+
+	fn get_logs_filter<T: Trait>(&self, from_block: u64, to_block: u64) -> Vec<u8> {
+		let mut filter = b"{\"fromBlock\":\"0x".to_vec();
+		filter.extend_from_slice(&<Module<T>>::digits(from_block, 16));
+		filter.extend_from_slice(b"\",\"toBlock\":\"0x");
+		filter.extend_from_slice(&<Module<T>>::digits(to_block, 16));
+		filter.extend_from_slice(b"\",\"address\":\"");
+		filter.extend_from_slice(&self.contract_addr);
+		filter.extend_from_slice(b"\",\"topics\":[[");
+		for (i, event) in [HTLC_EVENT, CLAIMED_EVENT, REFUNDED_EVENT].iter().enumerate() {
+			if i > 0 {
+				filter.push(b',');
+			}
+			filter.push(b'"');
+			filter.extend_from_slice(&<Module<T>>::to_hex(event.topic().as_bytes()));
+			filter.push(b'"');
+		}
+		filter.extend_from_slice(b"]]}");
+		filter
+	}
+}
+
+impl<T: Trait> LogSource<T> for JsonRpc {
+	fn head(&self, url: &str) -> Result<u64, OffchainErr> {
+		let response = Self::post::<T>(url, b"eth_blockNumber", b"")?;
+		<Module<T>>::result_number(&response)
+	}
+
+	fn block_hash(&self, url: &str, number: u64) -> Result<H256, OffchainErr> {
+		let mut params = b"\"0x".to_vec();
+		params.extend_from_slice(&<Module<T>>::digits(number, 16));
+		params.extend_from_slice(b"\",false");
+		let response = Self::post::<T>(url, b"eth_getBlockByNumber", &params)?;
+		<Module<T>>::result_block_hash(&response)
+	}
+
+	fn fetch_logs(&self, url: &str, from_block: u64, to_block: u64) -> Result<Vec<RawLog>, OffchainErr> {
+		let response = Self::post::<T>(url, b"eth_getLogs", &self.get_logs_filter::<T>(from_block, to_block))?;
+		<Module<T>>::raw_logs(&response)
+	}
+}
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
 pub struct EventHTLC<BlockNumber, Balance, Hash, AccountId>
@@ -82,6 +276,70 @@ where
 	event_type: HTLCType,
 }
 
+pub type EventHTLCOf<T> = EventHTLC<
+	<T as system::Trait>::BlockNumber,
+	<T as balances::Trait>::Balance,
+	<T as system::Trait>::Hash,
+	<T as system::Trait>::AccountId,
+>;
+
+/// An event with the hash of the ethereum block it is in, as the offchain workers report it.
+/// The hash goes to `EthBlocks`, `SwapData` keeps the `EventHTLC` layout it always had.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ReportedEvent<BlockNumber, Balance, Hash, AccountId>
+where
+	BlockNumber: PartialEq + Eq + Decode + Encode,
+{
+	htlc: EventHTLC<BlockNumber, Balance, Hash, AccountId>,
+	block_hash: H256,
+}
+
+pub type ReportedEventOf<T> = ReportedEvent<
+	<T as system::Trait>::BlockNumber,
+	<T as balances::Trait>::Balance,
+	<T as system::Trait>::Hash,
+	<T as system::Trait>::AccountId,
+>;
+
+/// Events an authority's offchain worker read from ethereum, signed with its `orin` key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EventReport<BlockNumber, Balance, Hash, AccountId>
+where
+	BlockNumber: PartialEq + Eq + Decode + Encode,
+{
+	/// block the offchain worker ran at
+	block_number: BlockNumber,
+	/// index of the reporter in `Authorities`
+	authority_index: u32,
+	/// `EventLogSource::event_name` the events were read from
+	event_name: Vec<u8>,
+	/// last ethereum block the events were read up to
+	to_block: u64,
+	events: Vec<ReportedEvent<BlockNumber, Balance, Hash, AccountId>>,
+	/// current hashes of the ethereum blocks in `PendingHeights`
+	block_hashes: Vec<(u64, H256)>,
+}
+
+/// Ethereum blocks a worker has reported for a source, kept in offchain local storage.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+struct FetchCursor<BlockNumber> {
+	to_block: u64,
+	/// block the report was submitted at
+	submitted_at: BlockNumber,
+}
+
+/// PRA locked in an htlc swap for an ethereum recipient, released to the escrow when claimed.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct PraLock<AccountId, Balance> {
+	sender: AccountId,
+	/// 20 byte ethereum address the ERC20 tokens go to
+	recipient_addr: Vec<u8>,
+	amount: Balance,
+	/// sha256(random_number || timestamp) as in ERC20HTLC.sol, the hashlock of the htlc swap
+	random_number_hash: H256,
+	timestamp: u64,
+}
+
 #[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
 pub enum HTLCStates {
 	INVALID,
@@ -108,7 +366,7 @@ pub enum HTLCType {
 //  automates offchain fetching every certain blocks
 pub const BLOCK_DURATION: u64 = 5;
 
-pub trait Trait: balances::Trait + timestamp::Trait {
+pub trait Trait: balances::Trait + timestamp::Trait + htlc::Trait<Currency = balances::Module<Self>> {
 	/// The identifier type for an authority.
 	type AuthorityId: Member + Parameter + RuntimeAppPublic + Default + Ord;
 	/// The overarching event type.
@@ -117,6 +375,18 @@ pub trait Trait: balances::Trait + timestamp::Trait {
 	type Call: From<Call<Self>>;
 	/// A transaction submitter.
 	type SubmitTransaction: SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+	/// Decimals of the ERC20 token on ethereum.
+	type TokenDecimals: Get<u8>;
+	/// Decimals of `Balance`, amounts are scaled between both exactly.
+	type NativeDecimals: Get<u8>;
+	/// Ethereum blocks a log must be behind the head before it is reported.
+	type ConfirmationDepth: Get<u64>;
+	/// Ethereum blocks after which a reorg can't invalidate the swaps of a block anymore.
+	type FinalityDepth: Get<u64>;
+	/// Blocks the data of a completed or expired swap is kept for.
+	type SwapRetention: Get<Self::BlockNumber>;
+	/// How many swaps `on_initialize` expires, and prunes, at most per block.
+	type MaxSweepsPerBlock: Get<u32>;
 }
 
 decl_storage! {
@@ -124,8 +394,35 @@ decl_storage! {
 		/// Stores the locked pra tokens
 		pub PraTokenAddr get(pra_token_addr): Option<T::AccountId>;
 
-		/// The current set of keys that may call update
-		pub Authorities get(authorities) config(): Vec<T::AccountId>;
+		/// The current set of keys that may report ethereum events
+		pub Authorities get(authorities) config(): Vec<T::AuthorityId>;
+
+		/// Number of authorities that must report an identical event before it is applied
+		pub Threshold get(threshold) config(): u32;
+
+		/// Key is (swap_id, event type), value is the block of the first vote and every reported version
+		/// of the event with its reporters
+		pub Votes get(votes): linked_map (T::Hash, HTLCType) => (T::BlockNumber, Vec<(ReportedEventOf<T>, Vec<T::AuthorityId>)>);
+
+		/// Key is (event_name, authority), value is the last ethereum block the authority has reported
+		pub Acknowledged get(acknowledged): map (Vec<u8>, T::AuthorityId) => u64;
+
+		/// Key is an ethereum block number, value is its hash and the swaps opened in it, until it is final
+		pub EthBlocks get(eth_blocks): map u64 => Option<(H256, Vec<T::Hash>)>;
+
+		/// The keys of `EthBlocks`
+		pub PendingHeights get(pending_heights): Vec<u64>;
+
+		/// Key is an ethereum block number, value is every other hash reported for it with its reporters
+		pub ReorgVotes get(reorg_votes): map u64 => Vec<(H256, Vec<T::AuthorityId>)>;
+
+		/// Key is (swap_id, event type), value is the block the event was applied at and the reported versions
+		/// that lost against it
+		pub Conflicts get(conflicts): map (T::Hash, HTLCType) => (T::BlockNumber, Vec<(ReportedEventOf<T>, Vec<T::AuthorityId>)>);
+
+		/// Key is the block `Votes` that never reached the threshold and `Conflicts` are dropped at, `SwapRetention`
+		/// after they were stored
+		pub StaleVotes get(stale_votes): map T::BlockNumber => Vec<(T::Hash, HTLCType)>;
 
 		/// Stores offchain request jobs
 		pub OcRequests get(oc_requests): Vec<EventLogSource>;
@@ -133,8 +430,33 @@ decl_storage! {
 		/// Key is swap_id, value is EventHTLC
 		pub SwapData get(swap_data): map T::Hash => Option<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>>;
 
-		/// Key is swap_id, Value is HTLCStates
+		/// Key is swap_id, Value is HTLCStates, kept after the swap closes so it settles only once
 		pub SwapStates get(swap_states): map T::Hash => Option<HTLCStates>;
+
+		/// Key is the block open swaps expire at, value is their swap_ids
+		pub ExpiryQueue get(expiry_queue): map T::BlockNumber => Vec<T::Hash>;
+
+		/// Key is the block the data of closed swaps is pruned at, value is their swap_ids
+		pub RetentionQueue get(retention_queue): map T::BlockNumber => Vec<T::Hash>;
+
+		/// First block swaps were queued at, the swaps opened before are queued by `queue_legacy_swaps`
+		pub QueuedSince get(queued_since): Option<T::BlockNumber>;
+
+		/// Key is the chain-local swap_id = sha256(random_number_hash || recipient_addr), value is the open PRA lock
+		pub PraLocks get(pra_locks): map H256 => Option<PraLock<T::AccountId, T::Balance>>;
+
+		/// Key is swap_id, Value is HTLCStates of the PRA lock
+		pub PraLockStates get(pra_lock_states): map H256 => Option<HTLCStates>;
+	}
+	add_extra_genesis {
+		// a zero threshold would let any single authority apply events
+		build(|config: &GenesisConfig<T>| {
+			assert!(config.threshold > 0, "oracle threshold must be at least 1");
+			assert!(
+				config.authorities.len() >= config.threshold as usize || config.authorities.is_empty(),
+				"oracle threshold must be reachable by the genesis authorities",
+			);
+		});
 	}
 }
 
@@ -145,6 +467,7 @@ decl_event!(
 		<T as system::Trait>::AccountId,
 		<T as system::Trait>::Hash,
 		<T as balances::Trait>::Balance,
+		<T as Trait>::AuthorityId,
 	{
 		///Setup pra_token_addr, event_name, event_url
 		Init(AccountId, Vec<u8>, Vec<u8>),
@@ -160,16 +483,124 @@ decl_event!(
 
 		///receiver_addr, eth_contract_addr, sender_addr, random_number_hash
 		Refund(AccountId, Vec<u8>, Hash, Vec<u8>, Vec<u8>),
+
+		///swap_id, receiver_addr, out_amount paid from pra_token_addr
+		Settled(Hash, AccountId, Balance),
+
+		///swap_id, sender, recipient_addr, amount, random_number_hash, timestamp, expire_height
+		Locked(H256, AccountId, Vec<u8>, Balance, H256, u64, BlockNumber),
+
+		///swap_id, recipient_addr, amount released to pra_token_addr, random_number
+		LockClaimed(H256, Vec<u8>, Balance, H256),
+
+		///swap_id, sender, amount
+		LockRefunded(H256, AccountId, Balance),
+
+		///swap_id, authority whose report differs from an earlier one
+		ReportConflict(Hash, AuthorityId),
+
+		///number of reports needed to apply an event
+		ThresholdSet(u32),
+
+		///ethereum block number, new hash of the block
+		Reorged(u64, H256),
+
+		///swap_id of a pending swap whose block was reorged away
+		Invalidated(Hash),
+
+		///swap_id of an open swap that passed its expire_height unclaimed
+		Expired(Hash),
 	}
 );
 
+decl_error! {
+	/// Error for the oracle module.
+	pub enum Error for Module<T: Trait> {
+		/// `kickoff_event_fetch` has not set the pra token address yet
+		PraTokenAddrNotSet,
+		/// The amount has more decimals than the other chain can represent
+		AmountPrecision,
+		/// The scaled amount does not fit the other chain
+		AmountOverflow,
+		/// The swap is unknown or not open anymore
+		SwapNotOpen,
+		/// The claim names another receiver than the locked swap
+		ReceiverMismatch,
+		/// The ethereum recipient is not a 20 byte address
+		InvalidRecipient,
+		/// A swap with this id exists already
+		SwapExists,
+		/// The random number does not match the hash lock
+		InvalidRandomNumber,
+		/// The report names no authority
+		NotAuthority,
+		/// The threshold is zero or above the number of authorities
+		InvalidThreshold,
+		/// The swap passed its expire_height before the claim
+		SwapExpired,
+		/// More swaps than `MaxSweepsPerBlock` at once
+		TooManySwaps,
+		/// Fewer authorities would remain than the threshold
+		BelowThreshold,
+	}
+}
+
+/// Failures of the offchain worker, they never reach a dispatchable.
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum OffchainErr {
+	/// `kickoff_event_fetch` has not set the pra token address yet
+	PraTokenAddrNotSet,
+	/// The configured event url is not a valid url of its source
+	InvalidUrl,
+	/// Adding a header to the http request failed
+	HttpHeaderFailed,
+	/// The http request failed or did not answer 200
+	HttpRequestFailed,
+	/// The http response body is empty
+	EmptyResponse,
+	/// The http response body is not the expected json
+	InvalidResponse,
+	/// The log does not decode as the contract event of its first topic
+	InvalidLog,
+	/// A timestamp in the event log is not valid hex
+	InvalidTimestamp,
+	/// A block number in the event log is not valid hex
+	InvalidBlockNumber,
+	/// The out amount is zero or differs from the pra amount
+	AmountMismatch,
+	/// The amount does not convert to a `Balance`
+	InvalidAmount,
+	/// The receiver in the event log is not a valid account
+	InvalidReceiver,
+	/// The receiver is the pra escrow account itself
+	ReceiverIsEscrow,
+	/// No local key could sign the report
+	SigningFailed,
+	/// Submitting the unsigned transaction failed
+	SubmitFailed,
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
 		fn deposit_event() = default;
 
+		const SwapRetention: T::BlockNumber = T::SwapRetention::get();
+		const MaxSweepsPerBlock: u32 = T::MaxSweepsPerBlock::get();
+
+		fn on_initialize(now: T::BlockNumber) {
+			if Self::queued_since().is_none() {
+				<QueuedSince<T>>::put(now);
+			}
+			Self::expire_swaps(now);
+			Self::prune_swaps(now);
+			Self::prune_votes(now);
+		}
+
 		// Initializing event fetch jobs
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
-		pub fn kickoff_event_fetch(origin, pra_token_addr: T::AccountId, event_name: Vec<u8>, event_url: Vec<u8>) -> dispatch_result {
+		pub fn kickoff_event_fetch(origin, pra_token_addr: T::AccountId, event_name: Vec<u8>, event_url: Vec<u8>, kind: LogSourceKind) -> DispatchResult {
 			ensure_root(origin)?;
 
 			runtime_io::misc::print_utf8(b"======== kickoff event fetch jobs");
@@ -179,6 +610,7 @@ decl_module! {
 			let event_src = EventLogSource {
 				event_name: event_name.clone(),
 				event_url: event_url.clone(),
+				kind,
 			};
 
 			<Self as Store>::OcRequests::mutate(|v|
@@ -191,7 +623,7 @@ decl_module! {
 
 		// Kill all event fetch jobs
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
-		pub fn kill_event_fetch(origin) -> dispatch_result {
+		pub fn kill_event_fetch(origin) -> DispatchResult {
 			ensure_root(origin)?;
 
 			runtime_io::misc::print_utf8(b"======== kill event fetch jobs");
@@ -200,8 +632,8 @@ decl_module! {
 			Ok(())
 		}
 
-		// Add a new authority to the set of keys that are allowed to update.
-		pub fn add_authority(origin, who: T::AccountId) -> dispatch_result {
+		// Add a new authority to the set of keys that are allowed to report events.
+		pub fn add_authority(origin, who: T::AuthorityId) -> DispatchResult {
 			ensure_root(origin)?;
 
 			if !Self::is_authority(&who) {
@@ -210,6 +642,132 @@ decl_module! {
 			Ok(())
 		}
 
+		// Remove an authority with its votes that have not been applied yet, so a compromised key can't help
+		// an event or a reorg over the threshold anymore. At least `threshold` authorities must remain.
+		pub fn remove_authority(origin, who: T::AuthorityId) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let mut authorities = Self::authorities();
+			authorities.retain(|i| *i != who);
+			ensure!(authorities.len() >= Self::threshold() as usize, Error::<T>::BelowThreshold);
+			<Authorities<T>>::put(authorities);
+
+			for (key, (since, mut candidates)) in <Votes<T>>::enumerate().collect::<Vec<_>>() {
+				if Self::strip_voter(&mut candidates, &who) {
+					<Votes<T>>::remove(&key);
+				} else {
+					<Votes<T>>::insert(&key, (since, candidates));
+				}
+			}
+			for height in Self::pending_heights() {
+				let mut candidates = Self::reorg_votes(height);
+				if Self::strip_voter(&mut candidates, &who) {
+					<ReorgVotes<T>>::remove(height);
+				} else {
+					<ReorgVotes<T>>::insert(height, candidates);
+				}
+			}
+			Ok(())
+		}
+
+		// Queue swaps stored before the expiry and retention queues existed, by the swap_ids of their HTLC events;
+		// storage maps can't be iterated on chain. Open ones expire and closed ones are pruned like later swaps.
+		pub fn queue_legacy_swaps(origin, swap_ids: Vec<T::Hash>) -> DispatchResult {
+			ensure_root(origin)?;
+
+			ensure!(swap_ids.len() <= T::MaxSweepsPerBlock::get() as usize, Error::<T>::TooManySwaps);
+			let since = Self::queued_since().unwrap_or_else(<system::Module<T>>::block_number);
+			for swap_id in swap_ids {
+				match Self::swap_data(swap_id) {
+					Some(swap) if swap.htlc_block_number < since => {},
+					_ => continue,
+				}
+				match Self::swap_states(swap_id) {
+					Some(HTLCStates::OPEN) => Self::schedule_expiry(Self::expires_at(swap_id).unwrap_or(since), swap_id),
+					Some(HTLCStates::COMPLETED) | Some(HTLCStates::EXPIRED) => Self::retain(swap_id),
+					_ => {},
+				}
+			}
+			Ok(())
+		}
+
+		// Set how many authorities must report an identical event before it is applied.
+		pub fn set_threshold(origin, threshold: u32) -> DispatchResult {
+			ensure_root(origin)?;
+
+			ensure!(threshold > 0 && threshold as usize <= Self::authorities().len(), Error::<T>::InvalidThreshold);
+			<Threshold>::put(threshold);
+
+			Self::deposit_event(RawEvent::ThresholdSet(threshold));
+			Ok(())
+		}
+
+		// Lock PRA for `recipient_addr` on ethereum in an htlc swap to pra_token_addr, claimable for `height_span` blocks
+		pub fn lock(origin, random_number_hash: H256, timestamp: u64, height_span: T::BlockNumber, recipient_addr: Vec<u8>, amount: T::Balance) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let pra_token_addr = Self::pra_token_addr().ok_or(Error::<T>::PraTokenAddrNotSet)?;
+			ensure!(recipient_addr.len() == 20, Error::<T>::InvalidRecipient);
+
+			let swap_id = Self::lock_swap_id(&random_number_hash, &recipient_addr);
+			ensure!(!<PraLockStates>::exists(swap_id), Error::<T>::SwapExists);
+			// the relayer must be able to pay the same value in tokens
+			Self::to_token_amount(amount)?;
+
+			<htlc::Module<T>>::open_swap(sender.clone(), random_number_hash, HashFunction::Sha256, height_span, pra_token_addr, amount)?;
+
+			let expire_height = <system::Module<T>>::block_number() + height_span;
+			<PraLocks<T>>::insert(swap_id, PraLock {
+				sender: sender.clone(),
+				recipient_addr: recipient_addr.clone(),
+				amount,
+				random_number_hash,
+				timestamp,
+			});
+			<PraLockStates>::insert(swap_id, HTLCStates::OPEN);
+
+			Self::deposit_event(RawEvent::Locked(swap_id, sender, recipient_addr, amount, random_number_hash, timestamp, expire_height));
+			Ok(())
+		}
+
+		// Release a PRA lock to pra_token_addr, the relayer pays the ERC20 tokens out on ethereum
+		pub fn claim(origin, swap_id: H256, random_number: H256) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+
+			ensure!(Self::pra_lock_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+			let lock = Self::pra_locks(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+			ensure!(Self::hash_lock(&random_number, lock.timestamp) == lock.random_number_hash, Error::<T>::InvalidRandomNumber);
+
+			// the swap may have been claimed through the htlc module, the escrow holds the funds then
+			if <htlc::Module<T>>::states(lock.random_number_hash) != Some(SwapState::Claimed) {
+				<htlc::Module<T>>::claim_swap(&Self::lock_preimage(&random_number, lock.timestamp))?;
+			}
+
+			<PraLocks<T>>::remove(swap_id);
+			<PraLockStates>::insert(swap_id, HTLCStates::COMPLETED);
+
+			Self::deposit_event(RawEvent::LockClaimed(swap_id, lock.recipient_addr, lock.amount, random_number));
+			Ok(())
+		}
+
+		// Return an expired PRA lock to its sender
+		pub fn refund(origin, swap_id: H256) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+
+			ensure!(Self::pra_lock_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+			let lock = Self::pra_locks(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+
+			if <htlc::Module<T>>::states(lock.random_number_hash) != Some(SwapState::Refunded) {
+				<htlc::Module<T>>::refund_swap(lock.random_number_hash)?;
+			}
+
+			<PraLocks<T>>::remove(swap_id);
+			<PraLockStates>::insert(swap_id, HTLCStates::EXPIRED);
+
+			Self::deposit_event(RawEvent::LockRefunded(swap_id, lock.sender, lock.amount));
+			Ok(())
+		}
+
 		// Runs after every block.
 		fn offchain_worker(now: T::BlockNumber) {
 			if BLOCK_DURATION > 0 && (TryInto::<u64>::try_into(now).ok().unwrap()) % BLOCK_DURATION == 0 {
@@ -217,412 +775,889 @@ decl_module! {
 			}
 		}
 
-		// Stores valid swap data and states
-		fn update_enevt_htlc(origin, htlcs: Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>>) {
-			// TODO: add auth control
+		// Votes for the reported events, an event is applied once `threshold` authorities reported it identically.
+		// The signature is checked in `validate_unsigned`.
+		fn report_events(origin, report: EventReport<T::BlockNumber, T::Balance, T::Hash, T::AccountId>,
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature) -> DispatchResult {
 			ensure_none(origin)?;
 
-			for htlc in htlcs {
-				match htlc.event_type {
-					HTLCType::HTLC => {
-						if !<SwapData<T>>::exists(htlc.swap_id) && !<SwapStates<T>>::exists(htlc.swap_id) {
-							<SwapData<T>>::insert(htlc.swap_id, &htlc);
-							<SwapStates<T>>::insert(htlc.swap_id, HTLCStates::OPEN);
-
-							Self::deposit_event(RawEvent::HTLC(htlc.receiver_addr, htlc.eth_contract_addr, htlc.htlc_block_number, htlc.expire_height,
-								htlc.random_number_hash, htlc.swap_id, htlc.sender_addr, htlc.out_amount, htlc.htlc_timestamp));
-						} else {
-							runtime_io::misc::print_utf8(b"error HTLC data already exist");
-						}
-					},
-					HTLCType::Claimed => {
-						if <SwapData<T>>::exists(htlc.swap_id) && <SwapStates<T>>::exists(htlc.swap_id) {
-							let swap_id = htlc.swap_id;
-							<SwapData<T>>::remove(&swap_id);
-							<SwapStates<T>>::remove(&swap_id);
-
-							Self::deposit_event(RawEvent::Claim(htlc.receiver_addr, htlc.eth_contract_addr, swap_id, htlc.sender_addr, htlc.random_number_hash));
-						} else {
-							runtime_io::misc::print_utf8(b"error Claimed swap_id not exist");
-						}
-					},
-					HTLCType::Refunded => {
-						if <SwapData<T>>::exists(htlc.swap_id) && <SwapStates<T>>::exists(htlc.swap_id) {
-							let swap_id = htlc.swap_id;
-							<SwapData<T>>::remove(&swap_id);
-							<SwapStates<T>>::remove(&swap_id);
-
-							Self::deposit_event(RawEvent::Refund(htlc.receiver_addr, htlc.eth_contract_addr, swap_id, htlc.sender_addr, htlc.random_number_hash));
-						} else {
-							runtime_io::misc::print_utf8(b"error Refunded swap_id not exist");
-						}
-					},
-					_ => return Err("error not valid htlc event_type")
-				}
+			let authority = Self::authorities().get(report.authority_index as usize).cloned().ok_or(Error::<T>::NotAuthority)?;
+			<Acknowledged<T>>::mutate((report.event_name.clone(), authority.clone()), |b| *b = cmp::max(*b, report.to_block));
+			for event in report.events {
+				Self::vote(&authority, event);
 			}
+			for (height, hash) in report.block_hashes {
+				Self::vote_block_hash(&authority, height, hash);
+			}
+			Self::finalize_blocks(&report.event_name);
+			Ok(())
 		}
 	}
 }
 
 impl<T: Trait> Module<T> {
 	fn offchain_events(now: T::BlockNumber) {
-		let mut fetch_success = true;
+		if !Self::acquire_fetch_lock() {
+			runtime_io::misc::print_utf8(b"event fetch still running");
+			return;
+		}
+
 		for fetch_info in Self::oc_requests() {
-			let res = Self::fetch_events(fetch_info.event_name, fetch_info.event_url);
-			if res.is_ok() {
-				fetch_success = false;
+			if Self::fetch_events(now, fetch_info).is_err() {
+				runtime_io::misc::print_utf8(b"event fetch failed");
 			}
 		}
 
-		if fetch_success {
-			//TODO make sure run once only
-			//Self::deposit_event(RawEvent::Kill(pra_token_addr, event_name, event_url));
-			<Self as Store>::OcRequests::kill();
-		}
+		Self::release_fetch_lock();
 	}
 
-	fn fetch_events(src: Vec<u8>, remote_url: Vec<u8>) -> Result<(), &'static str> {
+	fn fetch_events(now: T::BlockNumber, fetch_info: EventLogSource) -> Result<(), OffchainErr> {
 		let pra_token_addr = Self::pra_token_addr();
-		ensure!(pra_token_addr.is_some(), "pra_token_addr can not be empty");
+		ensure!(pra_token_addr.is_some(), OffchainErr::PraTokenAddrNotSet);
+
+		let keys = Self::local_authority_keys().collect::<Vec<_>>();
+		if keys.is_empty() {
+			return Ok(());
+		}
+
+		let src = fetch_info.event_name;
+		let from_block = Self::next_from_block(now, &src, &keys);
+		let url = core::str::from_utf8(&fetch_info.event_url).map_err(|_| OffchainErr::InvalidUrl)?;
+		let source: Box<dyn LogSource<T>> = match fetch_info.kind {
+			LogSourceKind::Etherscan => Box::new(Etherscan),
+			LogSourceKind::JsonRpc(contract_addr) => Box::new(JsonRpc { contract_addr }),
+		};
+
+		// only blocks `ConfirmationDepth` behind the head are read
+		let confirmed = source.head(url)?.saturating_sub(T::ConfirmationDepth::get());
+		let logs = if from_block <= confirmed {
+			source.fetch_logs(url, from_block, confirmed)?
+		} else {
+			Vec::new()
+		};
+		// a full page may end inside a block, read that block again next time; counted before parsing, the logs
+		// that don't parse are part of the page too
+		let full_page = logs.len() >= MAX_LOGS;
+		let highest = logs.iter().filter_map(|log| Self::hex_number(&log.block_number)).max();
+		let mut htlcs = Self::parse_data(logs);
+
+		let block_of = |event: &ReportedEventOf<T>| TryInto::<u64>::try_into(event.htlc.event_block_number).ok().unwrap_or(0);
+		let mut to_block = cmp::max(confirmed, from_block - 1);
+		if let Some(highest) = highest {
+			if full_page && highest > from_block {
+				htlcs.retain(|event| block_of(event) < highest);
+				to_block = highest - 1;
+			}
+		}
+
+		// etherscan logs don't name their block hash
+		let mut hashes: Vec<(u64, H256)> = Vec::new();
+		for event in htlcs.iter_mut().filter(|event| event.block_hash.is_zero()) {
+			event.block_hash = Self::cached_block_hash(&*source, url, block_of(event), &mut hashes)?;
+		}
+		// the current hashes of the blocks pending swaps were seen in, to notice reorgs
+		let mut block_hashes = Vec::new();
+		for height in Self::pending_heights().into_iter().filter(|height| *height <= confirmed) {
+			block_hashes.push((height, Self::cached_block_hash(&*source, url, height, &mut hashes)?));
+		}
+
+		if to_block < from_block && block_hashes.is_empty() {
+			return Ok(());
+		}
 
-		let url = core::str::from_utf8(&remote_url).unwrap();
-		let res = Self::http_request_get(&url, None);
-		if let Ok(buf) = res {
-			let htlcs = Self::parse_data(buf);
+		// every authority key in the local keystore reports on its own
+		for (authority_index, key) in keys {
+			let report = EventReport {
+				block_number: now,
+				authority_index,
+				event_name: src.clone(),
+				to_block,
+				events: htlcs.clone(),
+				block_hashes: block_hashes.clone(),
+			};
+			let signature = key.sign(&report.encode()).ok_or(OffchainErr::SigningFailed)?;
 
-			let call = Call::update_enevt_htlc(htlcs);
+			let call = Call::report_events(report, signature);
 			let result = T::SubmitTransaction::submit_unsigned(call);
 			match result {
 				Ok(_) => runtime_io::misc::print_utf8(b"execute off-chain worker success"),
 				Err(_) => {
 					runtime_io::misc::print_utf8(b"execute off-chain worker failed!");
-					return Err("error happens when submit unsigned transaction")
+					return Err(OffchainErr::SubmitFailed)
 				},
 			}
 		}
+
+		let cursor = FetchCursor { to_block, submitted_at: now };
+		runtime_io::offchain::local_storage_set(StorageKind::PERSISTENT, &Self::cursor_key(&src), &cursor.encode());
 		Ok(())
 	}
 
-	fn parse_data(res: Vec<u8>) -> Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>> {
-		runtime_io::misc::print_utf8(&res);
+	fn cached_block_hash(source: &dyn LogSource<T>, url: &str, number: u64, hashes: &mut Vec<(u64, H256)>) -> Result<H256, OffchainErr> {
+		if let Some((_, hash)) = hashes.iter().find(|(n, _)| *n == number) {
+			return Ok(*hash);
+		}
+		let hash = source.block_hash(url, number)?;
+		hashes.push((number, hash));
+		Ok(hash)
+	}
 
-		let mut vec_results: Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>> = Vec::new();
+	// first ethereum block to fetch for `src`: after the local cursor while its reports may still be
+	// in the pool, after the range the chain acknowledged for all local keys otherwise
+	fn next_from_block(now: T::BlockNumber, src: &[u8], keys: &[(u32, T::AuthorityId)]) -> u64 {
+		let acknowledged = keys.iter()
+			.map(|(_, key)| Self::acknowledged((src.to_vec(), key.clone())))
+			.min()
+			.unwrap_or(0);
+
+		let cursor = runtime_io::offchain::local_storage_get(StorageKind::PERSISTENT, &Self::cursor_key(src))
+			.and_then(|v| FetchCursor::<T::BlockNumber>::decode(&mut &v[..]).ok());
+		match cursor {
+			Some(cursor) if cursor.to_block > acknowledged
+				&& now <= cursor.submitted_at + T::BlockNumber::from(BLOCK_DURATION as u32) => cursor.to_block + 1,
+			_ => acknowledged + 1,
+		}
+	}
 
-		let json_str = core::str::from_utf8(&res);
-		if json_str.is_err() {
-			runtime_io::misc::print_utf8(b"error parse json from utf8");
-			return vec_results;
+	fn cursor_key(src: &[u8]) -> Vec<u8> {
+		let mut key = DB_CURSOR_PREFIX.to_vec();
+		key.extend_from_slice(src);
+		key
+	}
+
+	// the lock holds the unix millis it expires at, so a crashed worker doesn't block the next ones
+	fn acquire_fetch_lock() -> bool {
+		let now = runtime_io::offchain::timestamp().unix_millis();
+		let current = runtime_io::offchain::local_storage_get(StorageKind::PERSISTENT, DB_FETCH_LOCK);
+		if let Some(expires) = current.as_ref().and_then(|v| u64::decode(&mut &v[..]).ok()) {
+			if expires > now {
+				return false;
+			}
 		}
 
-		if let Ok(json_val) = simple_json::parse_json(json_str.unwrap()) {
-			let mut message = Vec::new();
-			let mut status = Vec::new();
-			let mut results = Vec::new();
+		runtime_io::offchain::local_storage_compare_and_set(
+			StorageKind::PERSISTENT,
+			DB_FETCH_LOCK,
+			current.as_ref().map(Vec::as_slice),
+			&(now + FETCH_LOCK_MILLIS).encode(),
+		)
+	}
 
-			json_val
-			.get_object()
-			.iter()
-			.filter(|(k, _)| {
-				let key: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-				KEY_MESSAGE.as_bytes().to_vec() == key
-				|| KEY_STATUS.as_bytes().to_vec() == key
-				|| KEY_RESULT.as_bytes().to_vec() == key
+	fn release_fetch_lock() {
+		runtime_io::offchain::local_storage_set(StorageKind::PERSISTENT, DB_FETCH_LOCK, &0u64.encode());
+	}
+
+	// `n` in base 10 or 16 without leading zeros
+	fn digits(mut n: u64, radix: u64) -> Vec<u8> {
+		let mut digits = Vec::new();
+		loop {
+			digits.push(HEX_DIGITS[(n % radix) as usize]);
+			n /= radix;
+			if n == 0 {
+				break;
+			}
+		}
+		digits.reverse();
+		digits
+	}
+
+	// 0x prefixed hex of `bytes`
+	fn to_hex(bytes: &[u8]) -> Vec<u8> {
+		let mut hex = STR_PREFIX.as_bytes().to_vec();
+		for byte in bytes {
+			hex.push(HEX_DIGITS[(byte >> 4) as usize]);
+			hex.push(HEX_DIGITS[(byte & 0xf) as usize]);
+		}
+		hex
+	}
+
+	// the bytes of 0x prefixed hex data
+	fn hex_bytes(hex: &[u8]) -> Option<Vec<u8>> {
+		if !hex.starts_with(STR_PREFIX.as_bytes()) {
+			return None;
+		}
+		Vec::from_hex(&hex[STR_PREFIX.len()..]).ok()
+	}
+
+	// a 0x prefixed hex quantity
+	fn hex_number(hex: &[u8]) -> Option<u64> {
+		if !hex.starts_with(STR_PREFIX.as_bytes()) {
+			return None;
+		}
+		let digits = core::str::from_utf8(&hex[STR_PREFIX.len()..]).ok()?;
+		u64::from_str_radix(digits, 16).ok()
+	}
+
+	// (index in `Authorities`, key) of the authorities whose keys are in the local keystore
+	fn local_authority_keys() -> impl Iterator<Item=(u32, T::AuthorityId)> {
+		let mut local_keys = T::AuthorityId::all();
+		local_keys.sort();
+
+		Self::authorities().into_iter()
+			.enumerate()
+			.filter_map(move |(index, authority)| {
+				local_keys.binary_search(&authority)
+					.ok()
+					.map(|location| (index as u32, local_keys[location].clone()))
 			})
-			.for_each(|(k, v)| {
-				let vec_of_u8s: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-				let key = core::str::from_utf8(&vec_of_u8s).unwrap();
-
-				if key == KEY_MESSAGE {
-					if let JsonValue::String(obj) = v {
-						message = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-					}
-				} else if key == KEY_STATUS {
-					if let JsonValue::String(obj) = v {
-						status = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-					}
-				} else if key == KEY_RESULT {
-					if let JsonValue::Array(array) = v {
-						results = array.to_vec();
-					}
-				}
-			});
+	}
+
+	fn parse_json(body: &[u8]) -> Result<JsonValue, OffchainErr> {
+		runtime_io::misc::print_utf8(body);
+
+		let json_str = core::str::from_utf8(body).map_err(|_| OffchainErr::InvalidResponse)?;
+		simple_json::parse_json(json_str).map_err(|_| OffchainErr::InvalidResponse)
+	}
+
+	fn json_field<'a>(value: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+		match value {
+			JsonValue::Object(fields) => fields.iter()
+				.find(|(k, _)| k.iter().map(|c| *c as u8).eq(key.bytes()))
+				.map(|(_, v)| v),
+			_ => None,
+		}
+	}
+
+	fn json_string(value: &JsonValue) -> Option<Vec<u8>> {
+		match value {
+			JsonValue::String(chars) => Some(chars.iter().map(|c| *c as u8).collect()),
+			_ => None,
+		}
+	}
+
+	// the `result` quantity of a json rpc response
+	fn result_number(response: &JsonValue) -> Result<u64, OffchainErr> {
+		Self::json_field(response, KEY_RESULT)
+			.and_then(Self::json_string)
+			.and_then(|number| Self::hex_number(&number))
+			.ok_or(OffchainErr::InvalidResponse)
+	}
+
+	// the hash of the block in the `result` of a json rpc response
+	fn result_block_hash(response: &JsonValue) -> Result<H256, OffchainErr> {
+		Self::json_field(response, KEY_RESULT)
+			.and_then(|block| Self::json_field(block, KEY_HASH))
+			.and_then(Self::json_string)
+			.and_then(|hash| Self::hex_bytes(&hash))
+			.filter(|hash| hash.len() == 32)
+			.map(|hash| H256::from_slice(&hash))
+			.ok_or(OffchainErr::InvalidResponse)
+	}
 
-			if status != b"1" || message != b"OK" {
-				runtime_io::misc::print_utf8(b"err not valid status or message");
-				return vec_results;
+	// the log objects in the `result` array of a response, the same for etherscan and json rpc
+	fn raw_logs(response: &JsonValue) -> Result<Vec<RawLog>, OffchainErr> {
+		let results = match Self::json_field(response, KEY_RESULT) {
+			Some(JsonValue::Array(results)) => results,
+			_ => return Err(OffchainErr::InvalidResponse),
+		};
+
+		Ok(results.iter().map(|result| {
+			let string = |key| Self::json_field(result, key).and_then(Self::json_string).unwrap_or_default();
+			let topics = match Self::json_field(result, KEY_TOPICS) {
+				Some(JsonValue::Array(topics)) => topics.iter().filter_map(Self::json_string).collect(),
+				_ => Vec::new(),
+			};
+			let time_stamp = Self::json_field(result, KEY_TIME_STAMP)
+				.and_then(Self::json_string)
+				.unwrap_or_else(|| b"0x0".to_vec());
+
+			RawLog {
+				address: string(KEY_ADDRESS),
+				topics,
+				data: string(KEY_DATA),
+				block_number: string(KEY_BLOCK_NUMBER),
+				block_hash: string(KEY_BLOCK_HASH),
+				time_stamp,
+				tx_hash: string(KEY_TX_HASH),
+				tx_index: string(KEY_TX_INDEX),
 			}
+		}).collect())
+	}
 
-			for result in results.iter() {
-				let mut contract_addr = Vec::new();
-				let mut topics = Vec::new();
-				let mut data = Vec::new();
-				let mut event_block_number = Vec::new();
-				let mut event_time_stamp = Vec::new();
-				let mut tx_hash = Vec::new();
-				let mut tx_index = Vec::new();
-
-				result
-				.get_object()
-				.iter()
-				.filter(|(k, _)| {
-					let key: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-					KEY_ADDRESS.as_bytes().to_vec() == key
-						|| KEY_TOPICS.as_bytes().to_vec() == key
-						|| KEY_DATA.as_bytes().to_vec() == key
-						|| KEY_BLOCK_NUMBER.as_bytes().to_vec() == key
-						|| KEY_TIME_STAMP.as_bytes().to_vec() == key
-						|| KEY_TX_HASH.as_bytes().to_vec() == key
-						|| KEY_TX_INDEX.as_bytes().to_vec() == key
-				})
-				.for_each(|(k, v)| {
-					let vec_of_u8s: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-					let key = core::str::from_utf8(&vec_of_u8s).unwrap();
-
-					if key == KEY_ADDRESS {
-						if let JsonValue::String(obj) = v {
-							contract_addr = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_TOPICS {
-						if let JsonValue::Array(array) = v {
-							for i in array.iter() {
-								if let JsonValue::String(obj) = i {
-									topics.push(obj.iter().map(|c| *c as u8).collect::<Vec<u8>>());
-								}
-							}
-						}
-					} else if key == KEY_DATA {
-						if let JsonValue::String(obj) = v {
-							data = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_BLOCK_NUMBER {
-						if let JsonValue::String(obj) = v {
-							event_block_number = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_TIME_STAMP {
-						if let JsonValue::String(obj) = v {
-							event_time_stamp = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_TX_HASH {
-						if let JsonValue::String(obj) = v {
-							tx_hash = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_TX_INDEX {
-						if let JsonValue::String(obj) = v {
-							tx_index = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					}
-				});
+	// the logs of an etherscan `getLogs` response, which answers status 0 both when there are no logs
+	// and when it fails; failures are errors so the cursor does not skip the range
+	fn etherscan_logs(response: &JsonValue) -> Result<Vec<RawLog>, OffchainErr> {
+		let status = Self::json_field(response, KEY_STATUS).and_then(Self::json_string).unwrap_or_default();
+		let message = Self::json_field(response, KEY_MESSAGE).and_then(Self::json_string).unwrap_or_default();
 
-				if topics.len() == 0 {
-					runtime_io::misc::print_utf8(b"not valid htlc topics length");
-					continue;
-				}
+		if status == STATUS_OK.as_bytes() && message == MESSAGE_OK.as_bytes() {
+			Self::raw_logs(response)
+		} else if status == STATUS_NOTOK.as_bytes() && message == MESSAGE_NO_RECORDS.as_bytes() {
+			Ok(Vec::new())
+		} else {
+			runtime_io::misc::print_utf8(b"err not valid status or message");
+			Err(OffchainErr::InvalidResponse)
+		}
+	}
 
-				match core::str::from_utf8(&topics[0]).unwrap() {
-					EVENT_SIG_HTLC => {
-							if let Ok(htlc) = Self::parse_htlc_event(contract_addr, topics, data, event_block_number, event_time_stamp, tx_hash, tx_index) {
-								runtime_io::misc::print_utf8(b"========= push EVENT_SIG_HTLC ");
-								vec_results.push(htlc);
-							} else {
-								runtime_io::misc::print_utf8(b"not valid parse_htlc_event result");
-							}},
-					EVENT_SIG_REFUND => {
-							if let Ok(htlc) = Self::parse_refund_event(contract_addr, topics, data, event_block_number, event_time_stamp, tx_hash, tx_index) {
-								runtime_io::misc::print_utf8(b"========= push EVENT_SIG_REFUND ");
-								vec_results.push(htlc);
-							} else {
-								runtime_io::misc::print_utf8(b"not valid parse_refund_event result");
-							}},
-					EVENT_SIG_CLAIM => {
-							if let Ok(htlc) = Self::parse_claim_event(contract_addr, topics, data, event_block_number, event_time_stamp, tx_hash, tx_index) {
-								runtime_io::misc::print_utf8(b"========= push EVENT_SIG_CLAIM ");
-								vec_results.push(htlc);
-							} else {
-								runtime_io::misc::print_utf8(b"not valid parse_claim_event result");
-							}},
-					_ => runtime_io::misc::print_utf8(b"not valid event signature")
-				}
+	fn parse_data(logs: Vec<RawLog>) -> Vec<ReportedEventOf<T>> {
+		let mut vec_results: Vec<ReportedEventOf<T>> = Vec::new();
+		let (htlc_topic, claimed_topic, refunded_topic) = (HTLC_EVENT.topic(), CLAIMED_EVENT.topic(), REFUNDED_EVENT.topic());
+
+		for log in logs {
+			let htlc = Self::parse_log(log, &htlc_topic, &claimed_topic, &refunded_topic);
+			match htlc {
+				Ok(Some(htlc)) => vec_results.push(htlc),
+				Ok(None) => runtime_io::misc::print_utf8(b"not valid event signature"),
+				Err(_) => runtime_io::misc::print_utf8(b"not valid htlc event log"),
 			}
 		}
 
 		return vec_results;
 	}
 
-	fn parse_htlc_event(contract_addr: Vec<u8>, topics: Vec<Vec<u8>>, data: Vec<u8>,
-						event_block_number: Vec<u8>, event_time_stamp: Vec<u8>, tx_hash: Vec<u8>, tx_index: Vec<u8>)
-						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, &'static str> {
-
-		//indexed topics: _msgSender(Address); _recipientAddr(FixedBytes(32));_swapID(FixedBytes(32))
-		//topics[0] is EventSignature
-		let msg_sender = &topics[1][STR_PREFIX.len()..].to_vec();
-		let recipient_addr = &topics[2][STR_PREFIX.len()..].to_vec();
-		let swap_id = &topics[3][STR_PREFIX.len()..].to_vec();
-
-		let random_num_hash = &data[STR_PREFIX.len()..66].to_vec();
-		let htlc_time_stamp = &data[STR_PREFIX.len()+64..66+64].to_vec();
-		let expire_height = &data[STR_PREFIX.len()+64+64..66+64+64].to_vec();
-		let out_amount = &data[STR_PREFIX.len()+64+64+64..66+64+64+64].to_vec();
-		let pra_amount = &data[STR_PREFIX.len()+64+64+64+64..66+64+64+64+64].to_vec();
-		let receiver_addr = &data[STR_PREFIX.len()+64+64+64+64+64+64+64..].to_vec();
-
-		let event_ts = u64::from_str_radix(core::str::from_utf8(&event_time_stamp[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse event_time_stamp from utf8")?;
-		let htlc_ts = u64::from_str_radix(core::str::from_utf8(&htlc_time_stamp[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse htlc_time_stamp from utf8")?;
-		let event_block_num = u32::from_str_radix(core::str::from_utf8(&event_block_number[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse event_block_num from utf8")?;
-		let expire_block_num = u32::from_str_radix(core::str::from_utf8(&expire_height[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse event_block_num from utf8")?;
-		let event_out_amount = u32::from_str_radix(core::str::from_utf8(&out_amount[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse out_amount from utf8")?;
-		let event_pra_amount = u32::from_str_radix(core::str::from_utf8(&pra_amount[STR_PREFIX.len()..]).unwrap(), 16)
-				.map_err(|_| "err parse pra_amount from utf8")?;
-
-		ensure!(event_out_amount > 0 && event_out_amount == event_pra_amount, "not valid out_amount or pra_amount");
-
-		runtime_io::misc::print_utf8(b"parse_htlc_event receiver_addr");
-		runtime_io::misc::print_utf8(&receiver_addr[..]);
-
-		//issue #4310
-		let receiver_t = Vec::from_hex(&receiver_addr[STR_PREFIX.len()..]).map_err(|_| "err parse receiver_addr from utf8")?;
-		let receiver_accnt = <T::AccountId as Decode>::decode(&mut receiver_t.as_slice()).map_err(|_| "err parse receiver_t from utf8")?;
-		ensure!(receiver_accnt != Self::pra_token_addr().unwrap(), "Needs different accounts");
+	fn parse_log(log: RawLog, htlc_topic: &H256, claimed_topic: &H256, refunded_topic: &H256)
+		-> Result<Option<ReportedEventOf<T>>, OffchainErr> {
+		let topics = log.topics.iter()
+			.map(|topic| Self::hex_bytes(topic).filter(|t| t.len() == 32).map(|t| H256::from_slice(&t)))
+			.collect::<Option<Vec<_>>>()
+			.ok_or(OffchainErr::InvalidLog)?;
+		let data = Self::hex_bytes(&log.data).ok_or(OffchainErr::InvalidLog)?;
+		let event_block_number = Self::hex_number(&log.block_number)
+			.and_then(|number| u32::try_from(number).ok())
+			.ok_or(OffchainErr::InvalidBlockNumber)?;
+		// zero until the worker looks it up
+		let block_hash = match log.block_hash.len() {
+			0 => H256::zero(),
+			_ => Self::hex_bytes(&log.block_hash)
+				.filter(|hash| hash.len() == 32)
+				.map(|hash| H256::from_slice(&hash))
+				.ok_or(OffchainErr::InvalidLog)?,
+		};
+		let event_timestamp = Self::hex_number(&log.time_stamp).ok_or(OffchainErr::InvalidTimestamp)?;
+
+		let contract_addr = log.address;
+		let htlc = match topics.first() {
+			Some(topic) if topic == htlc_topic =>
+				Self::parse_htlc_event(contract_addr, &topics, &data, event_block_number, event_timestamp)?,
+			Some(topic) if topic == claimed_topic =>
+				Self::parse_claim_event(contract_addr, &topics, &data, event_block_number)?,
+			Some(topic) if topic == refunded_topic =>
+				Self::parse_refund_event(contract_addr, &topics, &data, event_block_number)?,
+			_ => return Ok(None),
+		};
+		Ok(Some(ReportedEvent { htlc, block_hash }))
+	}
+
+	fn parse_htlc_event(contract_addr: Vec<u8>, topics: &[H256], data: &[u8], event_block_number: u32, event_timestamp: u64)
+						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, OffchainErr> {
+		let tokens = HTLC_EVENT.decode(topics, data).map_err(|_| OffchainErr::InvalidLog)?;
+
+		let msg_sender = Self::token(&tokens, 0, Token::into_address)?;
+		let recipient_addr = Self::token(&tokens, 1, Token::into_address)?;
+		let swap_id = Self::token(&tokens, 2, Token::into_h256)?;
+		let random_number_hash = Self::token(&tokens, 3, Token::into_h256)?;
+		let htlc_timestamp = Self::token(&tokens, 4, Token::into_uint)?.low_u64();
+		let expire_height = Self::token(&tokens, 5, Token::into_uint)?;
+		let out_amount = Self::token(&tokens, 6, Token::into_uint)?;
+		let pra_amount = Self::token(&tokens, 7, Token::into_uint)?;
+		let receiver_addr = Self::token(&tokens, 8, Token::into_bytes)?;
+
+		ensure!(!out_amount.is_zero() && out_amount == pra_amount, OffchainErr::AmountMismatch);
+		ensure!(expire_height.bits() <= 32, OffchainErr::InvalidBlockNumber);
+		let expire_height = expire_height.low_u32().checked_sub(event_block_number).ok_or(OffchainErr::InvalidBlockNumber)?;
 
 		let htlc = EventHTLC {
 			eth_contract_addr: contract_addr,
-			event_block_number: T::BlockNumber::from(event_block_num),
+			event_block_number: T::BlockNumber::from(event_block_number),
 			htlc_block_number: <system::Module<T>>::block_number(),
-			out_amount: T::Balance::from(event_out_amount),
-			expire_height: expire_block_num - event_block_num,
-			random_number_hash: random_num_hash.clone(),
-			swap_id: T::Hashing::hash(&swap_id[..]),
-			event_timestamp: event_ts,
-			htlc_timestamp: htlc_ts,
-			sender_addr: msg_sender.clone(),
+			out_amount: Self::to_balance(out_amount).map_err(|_| OffchainErr::InvalidAmount)?,
+			expire_height,
+			random_number_hash: Self::word_hex(&random_number_hash),
+			swap_id: Self::to_swap_id(&swap_id),
+			event_timestamp,
+			htlc_timestamp,
+			sender_addr: Self::address_hex(msg_sender),
 			sender_chain_type: HTLCChain::ETHMain,
-			receiver_addr: receiver_accnt,
+			receiver_addr: Self::to_receiver(&receiver_addr)?,
 			receiver_chain_type: HTLCChain::PRA,
-			recipient_addr: recipient_addr.clone(),
+			recipient_addr: Self::address_hex(recipient_addr),
 			event_type: HTLCType::HTLC,
 		};
 		Ok(htlc)
 	}
 
-	fn parse_claim_event(contract_addr: Vec<u8>, topics: Vec<Vec<u8>>, data: Vec<u8>,
-						event_block_number: Vec<u8>, event_time_stamp: Vec<u8>, tx_hash: Vec<u8>, tx_index: Vec<u8>)
-						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, &'static str> {
-		//indexed topics: _msgSender(Address); _recipientAddr(FixedBytes(32));_swapID(FixedBytes(32))
-		let msg_sender = &topics[1][STR_PREFIX.len()..].to_vec();
-		let recipient_addr = &topics[2][STR_PREFIX.len()..].to_vec();
-		let swap_id = T::Hashing::hash(&topics[3][STR_PREFIX.len()..]);
+	fn parse_claim_event(contract_addr: Vec<u8>, topics: &[H256], data: &[u8], event_block_number: u32)
+						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, OffchainErr> {
+		let tokens = CLAIMED_EVENT.decode(topics, data).map_err(|_| OffchainErr::InvalidLog)?;
 
-		let random_num = &data[STR_PREFIX.len()..66].to_vec();
-		let receiver_addr = &data[STR_PREFIX.len()+64+64+64..].to_vec();
-
-		runtime_io::misc::print_utf8(b"parse_claim_event receiver_addr");
-		runtime_io::misc::print_utf8(&receiver_addr[..]);
-
-		let receiver_t = Vec::from_hex(&receiver_addr[STR_PREFIX.len()..]).map_err(|_| "err parse receiver_addr from utf8")?;
-		let receiver_accnt = <T::AccountId as Decode>::decode(&mut receiver_t.as_slice()).map_err(|_| "err parse receiver_t from utf8")?;
-		ensure!(receiver_accnt != Self::pra_token_addr().unwrap(), "Needs different accounts");
-
-		let event_block_num = u32::from_str_radix(core::str::from_utf8(&event_block_number[STR_PREFIX.len()..]).unwrap(), 16)
-		.map_err(|_| "err parse event_block_num from utf8")?;
+		let msg_sender = Self::token(&tokens, 0, Token::into_address)?;
+		let recipient_addr = Self::token(&tokens, 1, Token::into_address)?;
+		let swap_id = Self::token(&tokens, 2, Token::into_h256)?;
+		let random_number = Self::token(&tokens, 3, Token::into_h256)?;
+		let receiver_addr = Self::token(&tokens, 4, Token::into_bytes)?;
 
 		let htlc = EventHTLC {
 			eth_contract_addr: contract_addr,
-			event_block_number: T::BlockNumber::from(event_block_num),
+			event_block_number: T::BlockNumber::from(event_block_number),
 			htlc_block_number: <system::Module<T>>::block_number(),
 			out_amount: T::Balance::from(0u32),
 			expire_height: 0u32,
-			random_number_hash: random_num.clone(),
-			swap_id: swap_id.clone(),
+			random_number_hash: Self::word_hex(&random_number),
+			swap_id: Self::to_swap_id(&swap_id),
 			event_timestamp: 0u64,
 			htlc_timestamp: 0u64,
-			sender_addr: msg_sender.clone(),
+			sender_addr: Self::address_hex(msg_sender),
 			sender_chain_type: HTLCChain::ETHMain,
-			receiver_addr: receiver_accnt,
-			recipient_addr: recipient_addr.clone(),
+			receiver_addr: Self::to_receiver(&receiver_addr)?,
+			recipient_addr: Self::address_hex(recipient_addr),
 			receiver_chain_type: HTLCChain::PRA,
 			event_type: HTLCType::Claimed,
 		};
 		Ok(htlc)
 	}
 
-	fn parse_refund_event(contract_addr: Vec<u8>, topics: Vec<Vec<u8>>, data: Vec<u8>,
-						event_block_number: Vec<u8>, event_time_stamp: Vec<u8>, tx_hash: Vec<u8>, tx_index: Vec<u8>)
-						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, &'static str> {
-
-		//indexed topics: _msgSender(Address); _recipientAddr(FixedBytes(32));_swapID(FixedBytes(32))
-		let msg_sender = &topics[1][STR_PREFIX.len()..].to_vec();
-		let recipient_addr = &topics[2][STR_PREFIX.len()..].to_vec();
-		let swap_id = T::Hashing::hash(&topics[3][STR_PREFIX.len()..]);
+	fn parse_refund_event(contract_addr: Vec<u8>, topics: &[H256], data: &[u8], event_block_number: u32)
+						-> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>, OffchainErr> {
+		let tokens = REFUNDED_EVENT.decode(topics, data).map_err(|_| OffchainErr::InvalidLog)?;
 
-		let random_num_hash = &data[STR_PREFIX.len()..66].to_vec();
-		let receiver_addr = &data[STR_PREFIX.len()+64+64..].to_vec();
-
-		runtime_io::misc::print_utf8(b"parse_refund_event receiver_addr");
-		runtime_io::misc::print_utf8(&receiver_addr[..]);
-
-		let receiver_t = Vec::from_hex(&receiver_addr[STR_PREFIX.len()..]).map_err(|_| "err parse receiver_addr from utf8")?;
-		let receiver_accnt = <T::AccountId as Decode>::decode(&mut receiver_t.as_slice()).map_err(|_| "err parse receiver_t from utf8")?;
-		ensure!(receiver_accnt != Self::pra_token_addr().unwrap(), "Needs different accounts");
-
-		let event_block_num = u32::from_str_radix(core::str::from_utf8(&event_block_number[STR_PREFIX.len()..]).unwrap(), 16)
-			.map_err(|_| "err parse event_block_num from utf8")?;
+		let msg_sender = Self::token(&tokens, 0, Token::into_address)?;
+		let recipient_addr = Self::token(&tokens, 1, Token::into_address)?;
+		let swap_id = Self::token(&tokens, 2, Token::into_h256)?;
+		let random_number_hash = Self::token(&tokens, 3, Token::into_h256)?;
+		let receiver_addr = Self::token(&tokens, 4, Token::into_bytes)?;
 
 		let htlc = EventHTLC {
 			eth_contract_addr: contract_addr,
-			event_block_number: T::BlockNumber::from(event_block_num),
+			event_block_number: T::BlockNumber::from(event_block_number),
 			htlc_block_number: <system::Module<T>>::block_number(),
 			out_amount: T::Balance::from(0u32),
 			expire_height: 0u32,
-			random_number_hash: random_num_hash.clone(),
-			swap_id: swap_id.clone(),
+			random_number_hash: Self::word_hex(&random_number_hash),
+			swap_id: Self::to_swap_id(&swap_id),
 			event_timestamp: 0u64,
 			htlc_timestamp: 0u64,
-			sender_addr: msg_sender.clone(),
+			sender_addr: Self::address_hex(msg_sender),
 			sender_chain_type: HTLCChain::ETHMain,
-			receiver_addr: receiver_accnt,
-			recipient_addr: recipient_addr.clone(),
+			receiver_addr: Self::to_receiver(&receiver_addr)?,
+			recipient_addr: Self::address_hex(recipient_addr),
 			receiver_chain_type: HTLCChain::PRA,
 			event_type: HTLCType::Refunded,
 		};
 		Ok(htlc)
 	}
 
-	fn http_request_get(uri: &str, header: Option<(&str, &str)>) -> Result<Vec<u8>, &'static str> {
-		let id: HttpRequestId = runtime_io::offchain::http_request_start("GET", uri, &[0]).unwrap();
+	fn token<R>(tokens: &[Token], index: usize, into: fn(Token) -> Option<R>) -> Result<R, OffchainErr> {
+		tokens.get(index).cloned().and_then(into).ok_or(OffchainErr::InvalidLog)
+	}
+
+	/// The `Balance` worth the ERC20 `amount`.
+	pub fn to_balance(amount: U256) -> Result<T::Balance, Error<T>> {
+		let amount = Self::scale(amount, T::TokenDecimals::get(), T::NativeDecimals::get())?;
+		ensure!(amount.bits() <= 128, Error::<T>::AmountOverflow);
+		T::Balance::try_from(amount.low_u128()).map_err(|_| Error::<T>::AmountOverflow)
+	}
+
+	/// The ERC20 amount worth `balance`.
+	pub fn to_token_amount(balance: T::Balance) -> Result<U256, Error<T>> {
+		let balance = TryInto::<u128>::try_into(balance).map_err(|_| Error::<T>::AmountOverflow)?;
+		Self::scale(U256::from(balance), T::NativeDecimals::get(), T::TokenDecimals::get())
+	}
+
+	// `amount` with `from` decimals in `to` decimals, without rounding
+	fn scale(amount: U256, from: u8, to: u8) -> Result<U256, Error<T>> {
+		// 10^77 is the largest power of ten in a U256
+		let pow10 = |exp: u8| if exp <= 77 { Ok(U256::exp10(exp as usize)) } else { Err(Error::<T>::AmountOverflow) };
+		if from >= to {
+			let divisor = pow10(from - to)?;
+			ensure!((amount % divisor).is_zero(), Error::<T>::AmountPrecision);
+			Ok(amount / divisor)
+		} else {
+			amount.checked_mul(pow10(to - from)?).ok_or(Error::<T>::AmountOverflow)
+		}
+	}
+
+	// swaps stored before the abi decoder are keyed by the hash of the 0x-less hex of `_swapID`,
+	// and keep their hex fields, so their later events still find them
+	fn to_swap_id(swap_id: &H256) -> T::Hash {
+		T::Hashing::hash(&Self::word_hex(swap_id))
+	}
+
+	// 0x-less hex of a 32 byte topic or data word
+	fn word_hex(word: &H256) -> Vec<u8> {
+		Self::to_hex(word.as_bytes()).split_off(STR_PREFIX.len())
+	}
+
+	// 0x-less hex of the indexed topic an address is logged as
+	fn address_hex(address: H160) -> Vec<u8> {
+		let mut word = H256::zero();
+		word.as_bytes_mut()[12..].copy_from_slice(address.as_bytes());
+		Self::word_hex(&word)
+	}
+
+	// `_receiverAddr` is the 0x prefixed hex of the account
+	fn to_receiver(receiver_addr: &[u8]) -> Result<T::AccountId, OffchainErr> {
+		let receiver = Self::hex_bytes(receiver_addr).ok_or(OffchainErr::InvalidReceiver)?;
+		let receiver = T::AccountId::decode(&mut receiver.as_slice()).map_err(|_| OffchainErr::InvalidReceiver)?;
+		ensure!(Some(&receiver) != Self::pra_token_addr().as_ref(), OffchainErr::ReceiverIsEscrow);
+		Ok(receiver)
+	}
+
+	fn http_request_get(uri: &str, header: Option<(&str, &str)>) -> Result<Vec<u8>, OffchainErr> {
+		let id: HttpRequestId = runtime_io::offchain::http_request_start("GET", uri, &[0]).map_err(|_| OffchainErr::HttpRequestFailed)?;
 		let deadline = runtime_io::offchain::timestamp().add(Duration::from_millis(10_000));
 
 		if let Some((name, value)) = header {
 			match runtime_io::offchain::http_request_add_header(id, name, value) {
 				Ok(_) => (),
-				Err(_) => return Err("Add request header failed"),
+				Err(_) => return Err(OffchainErr::HttpHeaderFailed),
 			};
 		}
 
+		Self::http_response(id, deadline)
+	}
+
+	fn http_request_post(uri: &str, body: &[u8]) -> Result<Vec<u8>, OffchainErr> {
+		let id: HttpRequestId = runtime_io::offchain::http_request_start("POST", uri, &[0]).map_err(|_| OffchainErr::HttpRequestFailed)?;
+		let deadline = runtime_io::offchain::timestamp().add(Duration::from_millis(10_000));
+
+		runtime_io::offchain::http_request_add_header(id, "Content-Type", "application/json")
+			.map_err(|_| OffchainErr::HttpHeaderFailed)?;
+		runtime_io::offchain::http_request_write_body(id, body, Some(deadline)).map_err(|_| OffchainErr::HttpRequestFailed)?;
+		// an empty chunk ends the body
+		runtime_io::offchain::http_request_write_body(id, &[], Some(deadline)).map_err(|_| OffchainErr::HttpRequestFailed)?;
+
+		Self::http_response(id, deadline)
+	}
+
+	fn http_response(id: HttpRequestId, deadline: Timestamp) -> Result<Vec<u8>, OffchainErr> {
 		match runtime_io::offchain::http_response_wait(&[id], Some(deadline))[0] {
 			HttpRequestStatus::Finished(200) => (),
-			_ => return Err("Request failed"),
+			_ => return Err(OffchainErr::HttpRequestFailed),
 		}
 
 		let mut result: Vec<u8> = vec![];
+		let mut buffer = vec![0; 1024];
 		loop {
-		  let mut buffer = vec![0; 1024];
-		  let _read = runtime_io::offchain::http_response_read_body(id, &mut buffer, Some(deadline)).map_err(|_e| ());
-		  result = [&result[..], &buffer[..]].concat();
-		  if _read == Ok(0) { break }
+			let read = runtime_io::offchain::http_response_read_body(id, &mut buffer, Some(deadline))
+				.map_err(|_| OffchainErr::HttpRequestFailed)?;
+			if read == 0 { break }
+			result.extend_from_slice(&buffer[..read as usize]);
 		}
 		if result.len() > 0 {
 			return Ok(result);
 		} else {
-			return Err("Parse body failed");
+			return Err(OffchainErr::EmptyResponse);
+		}
+	}
+
+	// whether applying the event would change the swap
+	fn is_pending(htlc: &EventHTLCOf<T>) -> bool {
+		match htlc.event_type {
+			HTLCType::HTLC => Self::can_open(htlc.swap_id),
+			HTLCType::Claimed | HTLCType::Refunded => Self::swap_states(htlc.swap_id) == Some(HTLCStates::OPEN),
+		}
+	}
+
+	// count the authority's vote for the event, apply it once enough authorities agree
+	fn vote(authority: &T::AuthorityId, mut event: ReportedEventOf<T>) {
+		if !Self::is_pending(&event.htlc) {
+			return;
+		}
+		// workers see the event at different blocks, it counts from the block it is applied at
+		event.htlc.htlc_block_number = Zero::zero();
+
+		let key = (event.htlc.swap_id, event.htlc.event_type.clone());
+		let now = <system::Module<T>>::block_number();
+		let fresh = !<Votes<T>>::exists(&key);
+		let (since, mut candidates) = if fresh { (now, Vec::new()) } else { Self::votes(&key) };
+		if candidates.iter().any(|(_, voters)| voters.contains(authority)) {
+			return;
+		}
+
+		let position = match candidates.iter().position(|(candidate, _)| *candidate == event) {
+			Some(position) => position,
+			None => {
+				if !candidates.is_empty() {
+					Self::deposit_event(RawEvent::ReportConflict(event.htlc.swap_id, authority.clone()));
+				}
+				candidates.push((event, Vec::new()));
+				candidates.len() - 1
+			},
+		};
+		candidates[position].1.push(authority.clone());
+
+		if candidates[position].1.len() < Self::required_approvals() {
+			<Votes<T>>::insert(&key, (since, candidates));
+			if fresh {
+				Self::drop_votes_at(now, key);
+			}
+			return;
+		}
+
+		let (ReportedEvent { mut htlc, block_hash }, _) = candidates.remove(position);
+		<Votes<T>>::remove(&key);
+		if !candidates.is_empty() {
+			<Conflicts<T>>::insert(&key, (now, candidates));
+			Self::drop_votes_at(now, key);
+		}
+
+		htlc.htlc_block_number = now;
+		Self::apply_event(htlc, block_hash);
+	}
+
+	// forget the votes and conflicts of `key` stored at `at` once `SwapRetention` passed
+	fn drop_votes_at(at: T::BlockNumber, key: (T::Hash, HTLCType)) {
+		let until = at + cmp::max(T::SwapRetention::get(), One::one());
+		<StaleVotes<T>>::mutate(until, |keys| keys.push(key));
+	}
+
+	// drop the votes and conflicts stored `SwapRetention` ago, at most `MaxSweepsPerBlock`, the rest the next block;
+	// keys voted on again since have a later entry
+	fn prune_votes(now: T::BlockNumber) {
+		let mut due = <StaleVotes<T>>::take(now);
+		let rest = due.split_off(cmp::min(due.len(), T::MaxSweepsPerBlock::get() as usize));
+		if !rest.is_empty() {
+			<StaleVotes<T>>::mutate(now + One::one(), |keys| keys.extend(rest));
+		}
+
+		let stale = |since: T::BlockNumber| since + cmp::max(T::SwapRetention::get(), One::one()) <= now;
+		for key in due {
+			if <Votes<T>>::exists(&key) && stale(Self::votes(&key).0) {
+				<Votes<T>>::remove(&key);
+			}
+			if <Conflicts<T>>::exists(&key) && stale(Self::conflicts(&key).0) {
+				<Conflicts<T>>::remove(&key);
+			}
+		}
+	}
+
+	// remove `who` from the voters, and the candidates only it voted for, true once no candidate is left
+	fn strip_voter<C>(candidates: &mut Vec<(C, Vec<T::AuthorityId>)>, who: &T::AuthorityId) -> bool {
+		for (_, voters) in candidates.iter_mut() {
+			voters.retain(|voter| voter != who);
+		}
+		candidates.retain(|(_, voters)| !voters.is_empty());
+		candidates.is_empty()
+	}
+
+	// Stores valid swap data and states
+	fn apply_event(htlc: EventHTLCOf<T>, block_hash: H256) {
+		match htlc.event_type {
+			HTLCType::HTLC => {
+				if Self::can_open(htlc.swap_id) {
+					<SwapData<T>>::insert(htlc.swap_id, &htlc);
+					<SwapStates<T>>::insert(htlc.swap_id, HTLCStates::OPEN);
+					Self::schedule_expiry(htlc.htlc_block_number + T::BlockNumber::from(htlc.expire_height), htlc.swap_id);
+					let height = TryInto::<u64>::try_into(htlc.event_block_number).ok().unwrap_or(0);
+					Self::record_block(height, block_hash, htlc.swap_id);
+
+					Self::deposit_event(RawEvent::HTLC(htlc.receiver_addr, htlc.eth_contract_addr, htlc.htlc_block_number, htlc.expire_height,
+						htlc.random_number_hash, htlc.swap_id, htlc.sender_addr, htlc.out_amount, htlc.htlc_timestamp));
+				} else {
+					runtime_io::misc::print_utf8(b"error HTLC data already exist");
+				}
+			},
+			HTLCType::Claimed => {
+				if Self::settle_claim(htlc).is_err() {
+					runtime_io::misc::print_utf8(b"error Claimed swap not settled");
+				}
+			},
+			HTLCType::Refunded => {
+				if Self::close_refund(htlc).is_err() {
+					runtime_io::misc::print_utf8(b"error Refunded swap_id not open");
+				}
+			},
+		}
+	}
+
+	// a swap is opened once, or again after its block was reorged away
+	fn can_open(swap_id: T::Hash) -> bool {
+		match Self::swap_states(swap_id) {
+			None | Some(HTLCStates::INVALID) => true,
+			_ => false,
+		}
+	}
+
+	fn required_approvals() -> usize {
+		cmp::max(Self::threshold(), 1) as usize
+	}
+
+	// remember the block a swap was opened in until it is final
+	fn record_block(height: u64, hash: H256, swap_id: T::Hash) {
+		let mut swaps = match Self::eth_blocks(height) {
+			Some((recorded, swaps)) if recorded == hash => swaps,
+			Some(_) => {
+				Self::invalidate_block(height, hash);
+				Vec::new()
+			},
+			None => Vec::new(),
+		};
+		if swaps.is_empty() {
+			<PendingHeights>::mutate(|heights| heights.push(height));
+		}
+		swaps.push(swap_id);
+		<EthBlocks<T>>::insert(height, (hash, swaps));
+	}
+
+	// count the authority's vote for another hash of a pending block, invalidate the block once enough agree
+	fn vote_block_hash(authority: &T::AuthorityId, height: u64, hash: H256) {
+		match Self::eth_blocks(height) {
+			Some((recorded, _)) if recorded != hash => {},
+			_ => return,
+		}
+
+		let mut candidates = Self::reorg_votes(height);
+		if candidates.iter().any(|(_, voters)| voters.contains(authority)) {
+			return;
+		}
+		let position = match candidates.iter().position(|(candidate, _)| *candidate == hash) {
+			Some(position) => position,
+			None => {
+				candidates.push((hash, Vec::new()));
+				candidates.len() - 1
+			},
+		};
+		candidates[position].1.push(authority.clone());
+
+		if candidates[position].1.len() < Self::required_approvals() {
+			<ReorgVotes<T>>::insert(height, candidates);
+			return;
+		}
+		Self::invalidate_block(height, hash);
+	}
+
+	// the swaps still open from a reorged block didn't happen, they may be reported again from the new chain
+	fn invalidate_block(height: u64, new_hash: H256) {
+		if let Some((_, swaps)) = <EthBlocks<T>>::take(height) {
+			for swap_id in swaps {
+				if Self::swap_states(swap_id) == Some(HTLCStates::OPEN) {
+					<SwapData<T>>::remove(swap_id);
+					<SwapStates<T>>::insert(swap_id, HTLCStates::INVALID);
+					Self::deposit_event(RawEvent::Invalidated(swap_id));
+				}
+			}
+		}
+		<ReorgVotes<T>>::remove(height);
+		<PendingHeights>::mutate(|heights| heights.retain(|h| *h != height));
+		Self::deposit_event(RawEvent::Reorged(height, new_hash));
+	}
+
+	// forget the blocks `FinalityDepth` behind the range enough authorities read
+	fn finalize_blocks(event_name: &[u8]) {
+		let mut acknowledged = Self::authorities().into_iter()
+			.map(|authority| Self::acknowledged((event_name.to_vec(), authority)))
+			.collect::<Vec<_>>();
+		acknowledged.sort_unstable_by(|a, b| b.cmp(a));
+		let agreed = match acknowledged.get(Self::required_approvals() - 1) {
+			Some(agreed) => *agreed,
+			None => return,
+		};
+
+		let (finalized, pending): (Vec<u64>, Vec<u64>) = Self::pending_heights().into_iter()
+			.partition(|height| height.saturating_add(T::FinalityDepth::get()) <= agreed);
+		if finalized.is_empty() {
+			return;
+		}
+		for height in finalized {
+			<EthBlocks<T>>::remove(height);
+			<ReorgVotes<T>>::remove(height);
+		}
+		<PendingHeights>::put(pending);
+	}
+
+	// pay the locked out_amount from pra_token_addr to the receiver, once per swap_id
+	fn settle_claim(claim: EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>) -> DispatchResult {
+		let swap_id = claim.swap_id;
+		ensure!(Self::swap_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+		// the sweep may not have reached it yet
+		ensure!(Self::is_claimable(swap_id), Error::<T>::SwapExpired);
+		let swap = Self::swap_data(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+		ensure!(swap.receiver_addr == claim.receiver_addr, Error::<T>::ReceiverMismatch);
+		let pra_token_addr = Self::pra_token_addr().ok_or(Error::<T>::PraTokenAddrNotSet)?;
+
+		<balances::Module<T> as Currency<_>>::transfer(&pra_token_addr, &swap.receiver_addr, swap.out_amount, ExistenceRequirement::AllowDeath)?;
+
+		<SwapStates<T>>::insert(&swap_id, HTLCStates::COMPLETED);
+		Self::retain(swap_id);
+
+		Self::deposit_event(RawEvent::Claim(claim.receiver_addr, claim.eth_contract_addr, swap_id, claim.sender_addr, claim.random_number_hash));
+		Self::deposit_event(RawEvent::Settled(swap_id, swap.receiver_addr, swap.out_amount));
+		Ok(())
+	}
+
+	// the locked tokens went back on ethereum, close the swap without paying out
+	fn close_refund(refund: EventHTLC<T::BlockNumber, T::Balance, T::Hash, T::AccountId>) -> DispatchResult {
+		let swap_id = refund.swap_id;
+		ensure!(Self::swap_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+
+		<SwapStates<T>>::insert(&swap_id, HTLCStates::EXPIRED);
+		Self::retain(swap_id);
+
+		Self::deposit_event(RawEvent::Refund(refund.receiver_addr, refund.eth_contract_addr, swap_id, refund.sender_addr, refund.random_number_hash));
+		Ok(())
+	}
+
+	fn schedule_expiry(end: T::BlockNumber, swap_id: T::Hash) {
+		// the sweep of the current block may have run already
+		let next = <system::Module<T>>::block_number() + One::one();
+		<ExpiryQueue<T>>::mutate(cmp::max(end, next), |swaps| swaps.push(swap_id));
+	}
+
+	fn expires_at(swap_id: T::Hash) -> Option<T::BlockNumber> {
+		Self::swap_data(swap_id).map(|swap| swap.htlc_block_number + T::BlockNumber::from(swap.expire_height))
+	}
+
+	// move the open swaps expiring at `now` to EXPIRED, at most `MaxSweepsPerBlock`, the rest the next block
+	fn expire_swaps(now: T::BlockNumber) {
+		let mut due = <ExpiryQueue<T>>::take(now);
+		let rest = due.split_off(cmp::min(due.len(), T::MaxSweepsPerBlock::get() as usize));
+		if !rest.is_empty() {
+			<ExpiryQueue<T>>::mutate(now + One::one(), |swaps| swaps.extend(rest));
+		}
+
+		for swap_id in due {
+			// closed swaps, and swaps reopened after a reorg, left their earlier entry behind
+			if Self::swap_states(swap_id) != Some(HTLCStates::OPEN) || Self::expires_at(swap_id).map_or(true, |end| end > now) {
+				continue;
+			}
+			<SwapStates<T>>::insert(swap_id, HTLCStates::EXPIRED);
+			Self::retain(swap_id);
+			Self::deposit_event(RawEvent::Expired(swap_id));
 		}
 	}
 
+	// keep the data of a closed swap for `SwapRetention` blocks, at least until the next block's sweep
+	fn retain(swap_id: T::Hash) {
+		let until = <system::Module<T>>::block_number() + cmp::max(T::SwapRetention::get(), One::one());
+		<RetentionQueue<T>>::mutate(until, |swaps| swaps.push(swap_id));
+	}
+
+	// drop the data of swaps closed `SwapRetention` ago, at most `MaxSweepsPerBlock`, their state stays
+	// so they settle only once
+	fn prune_swaps(now: T::BlockNumber) {
+		let mut due = <RetentionQueue<T>>::take(now);
+		let rest = due.split_off(cmp::min(due.len(), T::MaxSweepsPerBlock::get() as usize));
+		if !rest.is_empty() {
+			<RetentionQueue<T>>::mutate(now + One::one(), |swaps| swaps.extend(rest));
+		}
+
+		for swap_id in due {
+			<SwapData<T>>::remove(swap_id);
+		}
+	}
+
+	// random_number || timestamp, `timestamp` packed big endian like abi.encodePacked(uint64)
+	fn lock_preimage(random_number: &H256, timestamp: u64) -> Vec<u8> {
+		let mut preimage = random_number.as_bytes().to_vec();
+		preimage.extend_from_slice(&timestamp.to_be_bytes());
+		preimage
+	}
+
+	fn hash_lock(random_number: &H256, timestamp: u64) -> H256 {
+		HashFunction::Sha256.hash(&Self::lock_preimage(random_number, timestamp))
+	}
+
+	// sha256(random_number_hash || recipient_addr) with the 20 raw bytes of the ethereum recipient. Only this chain
+	// keys locks by it: calSwapID of ERC20HTLC.sol packs its receiver as a string and gives a different id.
+	fn lock_swap_id(random_number_hash: &H256, recipient_addr: &[u8]) -> H256 {
+		let mut data = random_number_hash.as_bytes().to_vec();
+		data.extend_from_slice(recipient_addr);
+		H256::from(runtime_io::hashing::sha2_256(&data))
+	}
+
 	//Helper that confirms whether the given `AccountId` has auth
-	fn is_authority(who: &T::AccountId) -> bool {
+	fn is_authority(who: &T::AuthorityId) -> bool {
 		Self::authorities().into_iter().find(|i| i == who).is_some()
 	}
 
@@ -634,18 +1669,8 @@ impl<T: Trait> Module<T> {
 
 	//if HTLC claimable
 	fn is_claimable(swap_id: T::Hash) -> bool {
-		let state = Self::swap_states(swap_id);
-
-		if state.is_some() && state.unwrap() == HTLCStates::OPEN {
-			let swap = Self::swap_data(swap_id);
-			if swap.is_some() {
-				let swap = swap.unwrap();
-				if <system::Module<T>>::block_number() < swap.htlc_block_number + T::BlockNumber::from(swap.expire_height) {
-					return true;
-				}
-			}
-		}
-		false
+		Self::swap_states(swap_id) == Some(HTLCStates::OPEN)
+			&& Self::expires_at(swap_id).map_or(false, |end| <system::Module<T>>::block_number() < end)
 	}
 }
 
@@ -654,15 +1679,716 @@ impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
 
 	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
 		match call {
-			Call::update_enevt_htlc(_) => Ok(
-			ValidTransaction {
-				priority: TransactionPriority::max_value(),
-				requires: vec![],
-				provides: vec![0.encode()],
-				longevity: TransactionLongevity::max_value(),
-				propagate: true,
-			}),
+			Call::report_events(report, signature) => {
+				let current_block = <system::Module<T>>::block_number();
+				if report.block_number > current_block {
+					return InvalidTransaction::Future.into();
+				}
+				if report.block_number + T::BlockNumber::from(BLOCK_DURATION as u32) < current_block {
+					return InvalidTransaction::Stale.into();
+				}
+
+				let authority = match Self::authorities().get(report.authority_index as usize) {
+					Some(authority) => authority.clone(),
+					None => return InvalidTransaction::BadProof.into(),
+				};
+				let signature_valid = report.using_encoded(|encoded_report| {
+					authority.verify(&encoded_report, &signature)
+				});
+				if !signature_valid {
+					return InvalidTransaction::BadProof.into();
+				}
+
+				Ok(ValidTransaction {
+					priority: TransactionPriority::max_value(),
+					requires: vec![],
+					provides: vec![(report.block_number, authority, &report.event_name).encode()],
+					longevity: BLOCK_DURATION,
+					propagate: true,
+				})
+			},
 			_ => UnknownTransaction::NoUnsignedValidator.into(),
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::H256;
+	use node_primitives::{AccountId, Balance, BlockNumber};
+	use crate::{Runtime, Origin, Balances, System, CreationFee, PraTokenDecimals, NativeDecimals, constants::currency::DOLLARS};
+	use primitives::Pair;
+	use sp_runtime::{DispatchError, traits::{BlakeTwo256, OnInitialize}, transaction_validity::TransactionValidityError};
+	use support::{assert_ok, assert_noop, traits::Get, unsigned::ValidateUnsigned};
+
+	type Oracle = Module<Runtime>;
+
+	// keccak256 of the event signatures in ERC20HTLC.sol
+	const HTLC_TOPIC: &str = "0x5a0cc384a12a55445d4625db5d24f6a72177fd330644e2d4b3ea0ebd6f78c54d";
+	const CLAIMED_TOPIC: &str = "0x07a9dd1ef03da239626dc5c5bac1995991043d2b6e0e23ca789bbc0a16eb911f";
+	const REFUNDED_TOPIC: &str = "0x215e15eef6d0300f9e89d940198e4f7fc22e44b7c80118c03571cd96da6c6c98";
+
+	fn scaled<R>(result: Result<R, Error<Runtime>>) -> Result<R, DispatchError> {
+		result.map_err(Into::into)
+	}
+
+	fn authority_pair(seed: u8) -> primitives::sr25519::Pair {
+		primitives::sr25519::Pair::from_seed(&[seed; 32])
+	}
+
+	fn authority(seed: u8) -> sr25519::AuthorityId {
+		authority_pair(seed).public().into()
+	}
+
+	// the events as seen in ethereum block 0xe1e1..
+	fn reported(events: Vec<EventHTLC<BlockNumber, Balance, H256, AccountId>>) -> Vec<ReportedEvent<BlockNumber, Balance, H256, AccountId>> {
+		events.into_iter().map(|htlc| ReportedEvent { htlc, block_hash: H256::repeat_byte(0xe1) }).collect()
+	}
+
+	fn report(authority_index: u32, events: Vec<EventHTLC<BlockNumber, Balance, H256, AccountId>>) -> DispatchResult {
+		report_events(authority_index, reported(events))
+	}
+
+	fn report_events(authority_index: u32, events: Vec<ReportedEvent<BlockNumber, Balance, H256, AccountId>>) -> DispatchResult {
+		let report = EventReport {
+			block_number: System::block_number(),
+			authority_index,
+			event_name: b"htlc".to_vec(),
+			to_block: 100,
+			events,
+			block_hashes: vec![],
+		};
+		Oracle::report_events(Origin::NONE, report, Default::default())
+	}
+
+	fn pra_token_addr() -> AccountId {
+		AccountId::from([1u8; 32])
+	}
+
+	fn receiver() -> AccountId {
+		AccountId::from([2u8; 32])
+	}
+
+	fn locker() -> AccountId {
+		AccountId::from([4u8; 32])
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		balances::GenesisConfig::<Runtime> {
+			balances: vec![(pra_token_addr(), 1_000 * DOLLARS), (locker(), 100 * DOLLARS)],
+			vesting: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn event(swap_id: H256, event_type: HTLCType, out_amount: Balance) -> EventHTLC<BlockNumber, Balance, H256, AccountId> {
+		EventHTLC {
+			eth_contract_addr: b"0xcontract".to_vec(),
+			htlc_block_number: 1,
+			event_block_number: 100,
+			expire_height: 200,
+			random_number_hash: b"0xsecret".to_vec(),
+			swap_id,
+			event_timestamp: 0,
+			htlc_timestamp: 0,
+			sender_addr: b"0xsender".to_vec(),
+			sender_chain_type: HTLCChain::ETHMain,
+			receiver_addr: receiver(),
+			receiver_chain_type: HTLCChain::PRA,
+			recipient_addr: b"0xrecipient".to_vec(),
+			out_amount,
+			event_type,
+		}
+	}
+
+	#[test]
+	fn should_settle_claimed_swaps_once() {
+		new_test_ext().execute_with(|| {
+			let amount = 10 * DOLLARS;
+			let (swap1, swap2) = (H256::repeat_byte(1), H256::repeat_byte(2));
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+
+			// nothing to claim before the lock is seen
+			assert_ok!(report(0, vec![event(swap1, HTLCType::Claimed, 0)]));
+			assert_eq!(Oracle::swap_states(swap1), None);
+
+			assert_ok!(report(0, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::OPEN));
+			assert_eq!(Balances::free_balance(&receiver()), 0);
+
+			// a claim for someone else does not pay out
+			let mut stolen = event(swap1, HTLCType::Claimed, 0);
+			stolen.receiver_addr = AccountId::from([3u8; 32]);
+			assert_ok!(report(0, vec![stolen]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::OPEN));
+
+			assert_ok!(report(0, vec![event(swap1, HTLCType::Claimed, 0)]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::COMPLETED));
+			assert!(Oracle::swap_data(swap1).is_some());
+			assert_eq!(Balances::free_balance(&receiver()), amount);
+			assert_eq!(Balances::free_balance(&pra_token_addr()), 1_000 * DOLLARS - amount - CreationFee::get());
+
+			// replayed events can't pay the swap twice
+			assert_ok!(report(0, vec![
+				event(swap1, HTLCType::Claimed, 0),
+				event(swap1, HTLCType::HTLC, amount),
+				event(swap1, HTLCType::Claimed, 0),
+			]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::COMPLETED));
+			assert_eq!(Balances::free_balance(&receiver()), amount);
+
+			// a refunded swap closes without payout
+			assert_ok!(report(0, vec![event(swap2, HTLCType::HTLC, amount)]));
+			assert_ok!(report(0, vec![event(swap2, HTLCType::Refunded, 0)]));
+			assert_eq!(Oracle::swap_states(swap2), Some(HTLCStates::EXPIRED));
+			assert!(Oracle::swap_data(swap2).is_some());
+
+			assert_ok!(report(0, vec![event(swap2, HTLCType::Claimed, 0)]));
+			assert_eq!(Oracle::swap_states(swap2), Some(HTLCStates::EXPIRED));
+			assert_eq!(Balances::free_balance(&receiver()), amount);
+		});
+	}
+
+	#[test]
+	fn should_lock_pra_for_ethereum() {
+		new_test_ext().execute_with(|| {
+			let amount = 10 * DOLLARS;
+			let recipient = vec![9u8; 20];
+			let timestamp = 1_577_836_800u64;
+			let (random1, random2) = (H256::repeat_byte(7), H256::repeat_byte(8));
+			let hash1 = Oracle::hash_lock(&random1, timestamp);
+			let hash2 = Oracle::hash_lock(&random2, timestamp);
+
+			assert_noop!(
+				Oracle::lock(Origin::signed(locker()), hash1, timestamp, 100, recipient.clone(), amount),
+				Error::<Runtime>::PraTokenAddrNotSet
+			);
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+
+			assert_noop!(
+				Oracle::lock(Origin::signed(locker()), hash1, timestamp, 100, vec![9u8; 19], amount),
+				Error::<Runtime>::InvalidRecipient
+			);
+			assert_noop!(
+				Oracle::lock(Origin::signed(locker()), hash1, timestamp, 1, recipient.clone(), amount),
+				htlc::Error::<Runtime, htlc::DefaultInstance>::InvalidTimelock
+			);
+
+			assert_ok!(Oracle::lock(Origin::signed(locker()), hash1, timestamp, 100, recipient.clone(), amount));
+			assert_eq!(Balances::reserved_balance(&locker()), amount);
+			assert_noop!(
+				Oracle::lock(Origin::signed(locker()), hash1, timestamp, 100, recipient.clone(), amount),
+				Error::<Runtime>::SwapExists
+			);
+
+			// the hash lock follows ERC20HTLC.sol, the swap id is local to this chain
+			let mut packed = random1.as_bytes().to_vec();
+			packed.extend_from_slice(&timestamp.to_be_bytes());
+			assert_eq!(hash1, H256::from(runtime_io::hashing::sha2_256(&packed)));
+			let swap1 = Oracle::lock_swap_id(&hash1, &recipient);
+			let mut packed = hash1.as_bytes().to_vec();
+			packed.extend_from_slice(&recipient);
+			assert_eq!(swap1, H256::from(runtime_io::hashing::sha2_256(&packed)));
+			assert_eq!(Oracle::pra_lock_states(swap1), Some(HTLCStates::OPEN));
+
+			assert_noop!(Oracle::claim(Origin::signed(receiver()), swap1, random2), Error::<Runtime>::InvalidRandomNumber);
+			assert_noop!(Oracle::refund(Origin::signed(locker()), swap1), htlc::Error::<Runtime, htlc::DefaultInstance>::SwapNotExpired);
+
+			// claiming releases the lock to the escrow
+			let escrow = Balances::free_balance(&pra_token_addr());
+			assert_ok!(Oracle::claim(Origin::signed(receiver()), swap1, random1));
+			assert_eq!(Balances::reserved_balance(&locker()), 0);
+			assert_eq!(Balances::free_balance(&pra_token_addr()), escrow + amount);
+			assert_eq!(Oracle::pra_lock_states(swap1), Some(HTLCStates::COMPLETED));
+			assert_noop!(Oracle::claim(Origin::signed(receiver()), swap1, random1), Error::<Runtime>::SwapNotOpen);
+
+			// after the timeout only a refund is left
+			assert_ok!(Oracle::lock(Origin::signed(locker()), hash2, timestamp, 100, recipient.clone(), amount));
+			let swap2 = Oracle::lock_swap_id(&hash2, &recipient);
+			System::set_block_number(System::block_number() + 100);
+			assert_noop!(Oracle::claim(Origin::signed(receiver()), swap2, random2), htlc::Error::<Runtime, htlc::DefaultInstance>::SwapExpired);
+			assert_ok!(Oracle::refund(Origin::signed(locker()), swap2));
+			assert_eq!(Balances::reserved_balance(&locker()), 0);
+			assert_eq!(Balances::free_balance(&locker()), 90 * DOLLARS);
+			assert_eq!(Oracle::pra_lock_states(swap2), Some(HTLCStates::EXPIRED));
+		});
+	}
+
+	#[test]
+	fn should_apply_reports_once_threshold_is_reached() {
+		new_test_ext().execute_with(|| {
+			let amount = 10 * DOLLARS;
+			let swap1 = H256::repeat_byte(1);
+			let key = (swap1, HTLCType::HTLC);
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+
+			assert_noop!(report(0, vec![event(swap1, HTLCType::HTLC, amount)]), Error::<Runtime>::NotAuthority);
+			for seed in 1..=3 {
+				assert_ok!(Oracle::add_authority(Origin::ROOT, authority(seed)));
+			}
+			assert_noop!(Oracle::set_threshold(Origin::ROOT, 0), Error::<Runtime>::InvalidThreshold);
+			assert_noop!(Oracle::set_threshold(Origin::ROOT, 4), Error::<Runtime>::InvalidThreshold);
+			assert_ok!(Oracle::set_threshold(Origin::ROOT, 2));
+
+			// a second report of the same authority doesn't count
+			assert_ok!(report(0, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_ok!(report(0, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_eq!(Oracle::swap_states(swap1), None);
+			assert_eq!(Oracle::votes(&key).1[0].1, vec![authority(1)]);
+
+			// a differing report is a conflict
+			assert_ok!(report(1, vec![event(swap1, HTLCType::HTLC, 2 * amount)]));
+			assert_eq!(Oracle::swap_states(swap1), None);
+			assert!(System::events().iter().any(|record|
+				record.event == crate::Event::oracle(RawEvent::ReportConflict(swap1, authority(2)))
+			));
+
+			assert_ok!(report(2, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::OPEN));
+			assert_eq!(Oracle::swap_data(swap1).unwrap().out_amount, amount);
+			assert_eq!(Oracle::swap_data(swap1).unwrap().htlc_block_number, System::block_number());
+			assert!(!<Votes<Runtime>>::exists(&key));
+			let (_, conflicts) = Oracle::conflicts(&key);
+			assert_eq!(conflicts.len(), 1);
+			assert_eq!(conflicts[0].0.htlc.out_amount, 2 * amount);
+			assert_eq!(conflicts[0].1, vec![authority(2)]);
+		});
+	}
+
+	#[test]
+	fn should_drop_votes_of_removed_authorities() {
+		new_test_ext().execute_with(|| {
+			let amount = 10 * DOLLARS;
+			let swap1 = H256::repeat_byte(1);
+			let swap2 = H256::repeat_byte(2);
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			for seed in 1..=4 {
+				assert_ok!(Oracle::add_authority(Origin::ROOT, authority(seed)));
+			}
+			assert_ok!(Oracle::set_threshold(Origin::ROOT, 3));
+
+			assert_ok!(report(0, vec![event(swap1, HTLCType::HTLC, amount), event(swap2, HTLCType::HTLC, amount)]));
+			assert_ok!(report(1, vec![event(swap2, HTLCType::HTLC, amount)]));
+			assert_ok!(Oracle::remove_authority(Origin::ROOT, authority(1)));
+			assert_noop!(Oracle::remove_authority(Origin::ROOT, authority(2)), Error::<Runtime>::BelowThreshold);
+
+			// the removed authority's vote no longer helps the events over the threshold
+			assert!(!<Votes<Runtime>>::exists(&(swap1, HTLCType::HTLC)));
+			assert_eq!(Oracle::votes(&(swap2, HTLCType::HTLC)).1[0].1, vec![authority(2)]);
+			assert_ok!(report(1, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_ok!(report(2, vec![event(swap1, HTLCType::HTLC, amount)]));
+			assert_eq!(Oracle::swap_states(swap1), None);
+		});
+	}
+
+	#[test]
+	fn should_prune_votes_that_never_reach_the_threshold() {
+		new_test_ext().execute_with(|| {
+			let key = (H256::repeat_byte(1), HTLCType::HTLC);
+			let retention = <Runtime as Trait>::SwapRetention::get();
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			for seed in 1..=2 {
+				assert_ok!(Oracle::add_authority(Origin::ROOT, authority(seed)));
+			}
+			assert_ok!(Oracle::set_threshold(Origin::ROOT, 2));
+
+			System::set_block_number(5);
+			assert_ok!(report(0, vec![event(key.0, HTLCType::HTLC, DOLLARS)]));
+			Oracle::on_initialize(4 + retention);
+			assert!(<Votes<Runtime>>::exists(&key));
+			Oracle::on_initialize(5 + retention);
+			assert!(!<Votes<Runtime>>::exists(&key));
+			assert_eq!(Oracle::stale_votes(5 + retention), vec![]);
+		});
+	}
+
+	#[test]
+	fn should_only_accept_signed_reports() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+
+			let signed = |report: EventReport<BlockNumber, Balance, H256, AccountId>, seed: u8| {
+				let signature = authority_pair(seed).sign(&report.encode()).into();
+				Oracle::validate_unsigned(&Call::report_events(report, signature))
+			};
+			let report_at = |block_number, authority_index| EventReport {
+				block_number,
+				authority_index,
+				event_name: b"htlc".to_vec(),
+				to_block: 100,
+				events: reported(vec![event(H256::repeat_byte(1), HTLCType::HTLC, DOLLARS)]),
+				block_hashes: vec![],
+			};
+
+			assert!(signed(report_at(10, 0), 1).is_ok());
+			assert_eq!(signed(report_at(10, 0), 2), Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof)));
+			assert_eq!(signed(report_at(10, 1), 1), Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof)));
+			assert_eq!(signed(report_at(11, 0), 1), Err(TransactionValidityError::Invalid(InvalidTransaction::Future)));
+			assert_eq!(signed(report_at(4, 0), 1), Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)));
+		});
+	}
+
+	#[test]
+	fn should_acknowledge_reported_ranges() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+			let acknowledged = || Oracle::acknowledged((b"htlc".to_vec(), authority(1)));
+			assert_eq!(acknowledged(), 0);
+
+			assert_ok!(report(0, vec![]));
+			assert_eq!(acknowledged(), 100);
+
+			// a late report of an older range doesn't move the acknowledgement back
+			let older = EventReport {
+				block_number: System::block_number(),
+				authority_index: 0,
+				event_name: b"htlc".to_vec(),
+				to_block: 50,
+				events: vec![],
+				block_hashes: vec![],
+			};
+			assert_ok!(Oracle::report_events(Origin::NONE, older, Default::default()));
+			assert_eq!(acknowledged(), 100);
+			assert_eq!(Oracle::acknowledged((b"other".to_vec(), authority(1))), 0);
+
+			assert_eq!(Oracle::digits(0, 10), b"0".to_vec());
+			assert_eq!(Oracle::digits(9_160_512, 10), b"9160512".to_vec());
+			assert_eq!(Oracle::digits(9_160_512, 16), b"8bc640".to_vec());
+			assert_eq!(Oracle::hex_number(b"0x8bc640"), Some(9_160_512));
+			assert_eq!(Oracle::hex_number(b"8bc640"), None);
+		});
+	}
+
+	#[test]
+	fn should_read_logs_of_both_sources() {
+		let log = |extra: &str| format!(
+			r#"{{"address":"0xcontract","topics":["{}","0x01"],"data":"0x02","blockNumber":"0x8bc640",{}"transactionHash":"0x03","transactionIndex":"0x0"}}"#,
+			HTLC_TOPIC, extra,
+		);
+		let etherscan = format!(r#"{{"status":"1","message":"OK","result":[{}]}}"#, log(r#""timeStamp":"0x5e0be100","#));
+		let json_rpc = format!(r#"{{"jsonrpc":"2.0","id":1,"result":[{}]}}"#, log(""));
+
+		let logs = Oracle::etherscan_logs(&Oracle::parse_json(etherscan.as_bytes()).unwrap()).unwrap();
+		assert_eq!(logs.len(), 1);
+		assert_eq!(logs[0].topics, vec![HTLC_TOPIC.as_bytes().to_vec(), b"0x01".to_vec()]);
+		assert_eq!(logs[0].block_number, b"0x8bc640".to_vec());
+		assert_eq!(logs[0].time_stamp, b"0x5e0be100".to_vec());
+
+		// json rpc logs carry no block time
+		let logs = Oracle::raw_logs(&Oracle::parse_json(json_rpc.as_bytes()).unwrap()).unwrap();
+		assert_eq!(logs[0].time_stamp, b"0x0".to_vec());
+		assert_eq!(logs[0].tx_hash, b"0x03".to_vec());
+
+		let error = Oracle::parse_json(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000}}"#).unwrap();
+		assert!(Oracle::raw_logs(&error).is_err());
+
+		// only "No records found" means an empty range, rate limits and bad keys must not move the cursor
+		let empty = Oracle::parse_json(br#"{"status":"0","message":"No records found","result":[]}"#).unwrap();
+		assert_eq!(Oracle::etherscan_logs(&empty).ok(), Some(Vec::new()));
+		for failure in [
+			&br#"{"status":"0","message":"NOTOK","result":"Max rate limit reached"}"#[..],
+			&br#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#[..],
+			&br#"{"result":[]}"#[..],
+		].iter() {
+			let response = Oracle::parse_json(failure).unwrap();
+			assert!(Oracle::etherscan_logs(&response).is_err());
+		}
+
+		let request = JsonRpc::request(b"eth_blockNumber", b"");
+		assert_eq!(request, br#"{"jsonrpc":"2.0","id":1,"method":"eth_blockNumber","params":[]}"#.to_vec());
+
+		let block = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{{"number":"0x8bc640","hash":"0x{}"}}}}"#, "e1".repeat(32));
+		assert_eq!(Oracle::result_block_hash(&Oracle::parse_json(block.as_bytes()).unwrap()).ok(), Some(H256::repeat_byte(0xe1)));
+		assert_eq!(
+			Etherscan::proxy_url::<Runtime>("https://api.etherscan.io/api?module=logs&action=getLogs&apikey=k", b"eth_blockNumber").ok(),
+			Some(b"https://api.etherscan.io/api?module=proxy&action=eth_blockNumber&apikey=k".to_vec())
+		);
+	}
+
+	#[test]
+	fn should_decode_erc20_htlc_logs() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Oracle::to_hex(HTLC_EVENT.topic().as_bytes()), HTLC_TOPIC.as_bytes().to_vec());
+			assert_eq!(Oracle::to_hex(CLAIMED_EVENT.topic().as_bytes()), CLAIMED_TOPIC.as_bytes().to_vec());
+			assert_eq!(Oracle::to_hex(REFUNDED_EVENT.topic().as_bytes()), REFUNDED_TOPIC.as_bytes().to_vec());
+
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			let receiver_hex = Oracle::to_hex(receiver().as_ref());
+			let word = |value: U256| {
+				let mut word = [0u8; 32];
+				value.to_big_endian(&mut word);
+				word.to_vec()
+			};
+			let amount = U256::from(100) * U256::exp10(PraTokenDecimals::get() as usize);
+
+			// HTLC of 100 tokens, more than fits a u32
+			let mut data = vec![0xab; 32];
+			for value in &[U256::from(1_577_836_800u64), U256::from(9_160_612u64), amount, amount, U256::from(0xc0), U256::from(receiver_hex.len())] {
+				data.extend(word(*value));
+			}
+			data.extend(&receiver_hex);
+			data.resize(data.len() + 30, 0);
+
+			let log = RawLog {
+				address: b"0xcontract".to_vec(),
+				topics: vec![
+					HTLC_TOPIC.as_bytes().to_vec(),
+					b"0x0000000000000000000000001111111111111111111111111111111111111111".to_vec(),
+					b"0x0000000000000000000000002222222222222222222222222222222222222222".to_vec(),
+					Oracle::to_hex(&[0x33; 32]),
+				],
+				data: Oracle::to_hex(&data),
+				block_number: b"0x8bc640".to_vec(),
+				block_hash: Oracle::to_hex(&[0xe1; 32]),
+				time_stamp: b"0x5e0be100".to_vec(),
+				tx_hash: b"0x01".to_vec(),
+				tx_index: b"0x0".to_vec(),
+			};
+
+			let htlcs = Oracle::parse_data(vec![log.clone()]);
+			assert_eq!(htlcs.len(), 1);
+			let htlc = &htlcs[0].htlc;
+			assert_eq!(htlc.event_type, HTLCType::HTLC);
+			assert_eq!(htlc.out_amount, 100 * DOLLARS);
+			// ids and hex fields as the chain stored them before the abi decoder
+			assert_eq!(htlc.swap_id, BlakeTwo256::hash(&[b'3'; 64]));
+			assert_eq!(htlc.receiver_addr, receiver());
+			assert_eq!(htlc.sender_addr, [vec![b'0'; 24], vec![b'1'; 40]].concat());
+			assert_eq!(htlc.recipient_addr, [vec![b'0'; 24], vec![b'2'; 40]].concat());
+			assert_eq!(htlc.random_number_hash, b"ab".repeat(32));
+			assert_eq!(htlc.expire_height, 9_160_612 - 9_160_512);
+			assert_eq!(htlc.htlc_timestamp, 1_577_836_800);
+			assert_eq!(htlcs[0].block_hash, H256::repeat_byte(0xe1));
+
+			// amounts must match
+			let mut mismatch = log.clone();
+			let mut bad = data.clone();
+			bad[5 * 32 - 1] ^= 1;
+			mismatch.data = Oracle::to_hex(&bad);
+			assert!(Oracle::parse_data(vec![mismatch]).is_empty());
+
+			// other events of the contract are skipped
+			let mut other = log;
+			other.topics[0] = Oracle::to_hex(&[1; 32]);
+			assert!(Oracle::parse_data(vec![other]).is_empty());
+		});
+	}
+
+	#[test]
+	fn should_invalidate_swaps_of_reorged_blocks() {
+		new_test_ext().execute_with(|| {
+			let swap = H256::repeat_byte(1);
+			let (old_hash, new_hash) = (H256::repeat_byte(0xe1), H256::repeat_byte(0xe2));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(2)));
+			assert_ok!(Oracle::set_threshold(Origin::ROOT, 2));
+
+			let report_hashes = |authority_index, to_block, block_hashes| {
+				let report = EventReport {
+					block_number: System::block_number(),
+					authority_index,
+					event_name: b"htlc".to_vec(),
+					to_block,
+					events: vec![],
+					block_hashes,
+				};
+				Oracle::report_events(Origin::NONE, report, Default::default())
+			};
+
+			assert_ok!(report(0, vec![event(swap, HTLCType::HTLC, DOLLARS)]));
+			assert_ok!(report(1, vec![event(swap, HTLCType::HTLC, DOLLARS)]));
+			assert_eq!(Oracle::swap_states(swap), Some(HTLCStates::OPEN));
+			assert_eq!(Oracle::eth_blocks(100), Some((old_hash, vec![swap])));
+			assert_eq!(Oracle::pending_heights(), vec![100]);
+
+			// the same hash or a single authority doesn't invalidate anything
+			assert_ok!(report_hashes(0, 100, vec![(100, old_hash)]));
+			assert_ok!(report_hashes(0, 100, vec![(100, new_hash)]));
+			assert_eq!(Oracle::swap_states(swap), Some(HTLCStates::OPEN));
+
+			assert_ok!(report_hashes(1, 100, vec![(100, new_hash)]));
+			assert_eq!(Oracle::swap_states(swap), Some(HTLCStates::INVALID));
+			assert!(Oracle::swap_data(swap).is_none());
+			assert_eq!(Oracle::eth_blocks(100), None);
+			assert!(Oracle::pending_heights().is_empty());
+
+			// the swap opens again once seen in the new block
+			let reopened = ReportedEvent { htlc: event(swap, HTLCType::HTLC, DOLLARS), block_hash: new_hash };
+			assert_ok!(report_events(0, vec![reopened.clone()]));
+			assert_ok!(report_events(1, vec![reopened]));
+			assert_eq!(Oracle::swap_states(swap), Some(HTLCStates::OPEN));
+			assert_eq!(Oracle::eth_blocks(100), Some((new_hash, vec![swap])));
+
+			// final once both authorities read `FinalityDepth` blocks past it
+			let final_block = 100 + <Runtime as Trait>::FinalityDepth::get();
+			assert_ok!(report_hashes(0, final_block, vec![]));
+			assert_eq!(Oracle::pending_heights(), vec![100]);
+			assert_ok!(report_hashes(1, final_block, vec![]));
+			assert!(Oracle::pending_heights().is_empty());
+			assert_eq!(Oracle::eth_blocks(100), None);
+
+			assert_ok!(report_hashes(0, final_block, vec![(100, old_hash)]));
+			assert_ok!(report_hashes(1, final_block, vec![(100, old_hash)]));
+			assert_eq!(Oracle::swap_states(swap), Some(HTLCStates::OPEN));
+		});
+	}
+
+	#[test]
+	fn should_expire_and_prune_swaps() {
+		new_test_ext().execute_with(|| {
+			let (swap1, swap2, swap3) = (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+			let htlc = |swap_id, expire_height| {
+				let mut htlc = event(swap_id, HTLCType::HTLC, DOLLARS);
+				htlc.expire_height = expire_height;
+				htlc
+			};
+
+			System::set_block_number(10);
+			assert_ok!(report(0, vec![htlc(swap1, 5), htlc(swap2, 20)]));
+			assert_eq!(Oracle::expiry_queue(15), vec![swap1]);
+			assert_eq!(Oracle::expiry_queue(30), vec![swap2]);
+
+			Oracle::on_initialize(14);
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::OPEN));
+
+			System::set_block_number(15);
+			Oracle::on_initialize(15);
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::EXPIRED));
+			assert!(Oracle::expiry_queue(15).is_empty());
+			let retention = <Runtime as Trait>::SwapRetention::get();
+			assert_eq!(Oracle::retention_queue(15 + retention), vec![swap1]);
+
+			// late claims don't pay out
+			assert_ok!(report(0, vec![event(swap1, HTLCType::Claimed, 0)]));
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::EXPIRED));
+			assert_eq!(Balances::free_balance(&receiver()), 0);
+
+			assert_ok!(report(0, vec![event(swap2, HTLCType::Claimed, 0)]));
+			assert_eq!(Oracle::swap_states(swap2), Some(HTLCStates::COMPLETED));
+			assert_eq!(Oracle::retention_queue(15 + retention), vec![swap1, swap2]);
+
+			// a completed swap leaves the queue without expiring
+			System::set_block_number(30);
+			Oracle::on_initialize(30);
+			assert!(Oracle::expiry_queue(30).is_empty());
+			assert_eq!(Oracle::swap_states(swap2), Some(HTLCStates::COMPLETED));
+
+			// past expire_height before the sweep reaches it
+			assert_ok!(report(0, vec![htlc(swap3, 1)]));
+			System::set_block_number(31);
+			assert_noop!(Oracle::settle_claim(event(swap3, HTLCType::Claimed, 0)), Error::<Runtime>::SwapExpired);
+			Oracle::on_initialize(31);
+			assert_eq!(Oracle::swap_states(swap3), Some(HTLCStates::EXPIRED));
+
+			// the records stay for the retention period, the states for good
+			System::set_block_number(15 + retention);
+			Oracle::on_initialize(15 + retention);
+			assert!(Oracle::swap_data(swap1).is_none());
+			assert!(Oracle::swap_data(swap2).is_none());
+			assert_eq!(Oracle::swap_states(swap1), Some(HTLCStates::EXPIRED));
+			assert_eq!(Oracle::swap_states(swap2), Some(HTLCStates::COMPLETED));
+			assert_eq!(Oracle::swap_states(swap3), Some(HTLCStates::EXPIRED));
+			assert!(Oracle::swap_data(swap3).is_some());
+		});
+	}
+
+	#[test]
+	fn should_sweep_at_most_max_per_block() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Oracle::kickoff_event_fetch(Origin::ROOT, pra_token_addr(), b"htlc".to_vec(), b"http://localhost".to_vec(), LogSourceKind::Etherscan));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+			let max = <Runtime as Trait>::MaxSweepsPerBlock::get() as u8;
+			let swaps = (0..=max).map(H256::repeat_byte).collect::<Vec<_>>();
+
+			System::set_block_number(10);
+			assert_ok!(report(0, swaps.iter().map(|swap_id| event(*swap_id, HTLCType::HTLC, DOLLARS)).collect()));
+			assert_eq!(Oracle::expiry_queue(210).len(), swaps.len());
+
+			// the one over the limit expires a block later
+			System::set_block_number(210);
+			Oracle::on_initialize(210);
+			assert!(Oracle::expiry_queue(210).is_empty());
+			assert_eq!(Oracle::expiry_queue(211), vec![swaps[max as usize]]);
+			assert_eq!(Oracle::swap_states(swaps[0]), Some(HTLCStates::EXPIRED));
+			assert_eq!(Oracle::swap_states(swaps[max as usize]), Some(HTLCStates::OPEN));
+
+			System::set_block_number(211);
+			Oracle::on_initialize(211);
+			assert_eq!(Oracle::swap_states(swaps[max as usize]), Some(HTLCStates::EXPIRED));
+		});
+	}
+
+	#[test]
+	fn should_queue_legacy_swaps() {
+		new_test_ext().execute_with(|| {
+			let (open, closed, queued) = (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+			// swaps stored before the queues existed
+			for (swap_id, state) in &[(open, HTLCStates::OPEN), (closed, HTLCStates::COMPLETED)] {
+				<SwapData<Runtime>>::insert(swap_id, event(*swap_id, HTLCType::HTLC, DOLLARS));
+				<SwapStates<Runtime>>::insert(swap_id, state.clone());
+			}
+
+			System::set_block_number(5);
+			Oracle::on_initialize(5);
+			assert_eq!(Oracle::queued_since(), Some(5));
+			assert_ok!(Oracle::add_authority(Origin::ROOT, authority(1)));
+			assert_ok!(report(0, vec![event(queued, HTLCType::HTLC, DOLLARS)]));
+			assert_eq!(Oracle::expiry_queue(205), vec![queued]);
+
+			let too_many = vec![open; <Runtime as Trait>::MaxSweepsPerBlock::get() as usize + 1];
+			assert_noop!(Oracle::queue_legacy_swaps(Origin::ROOT, too_many), Error::<Runtime>::TooManySwaps);
+
+			// the swap queued already isn't queued twice
+			assert_ok!(Oracle::queue_legacy_swaps(Origin::ROOT, vec![open, closed, queued]));
+			assert_eq!(Oracle::expiry_queue(201), vec![open]);
+			assert_eq!(Oracle::expiry_queue(205), vec![queued]);
+			let retention = <Runtime as Trait>::SwapRetention::get();
+			assert_eq!(Oracle::retention_queue(5 + retention), vec![closed]);
+
+			System::set_block_number(201);
+			Oracle::on_initialize(201);
+			assert_eq!(Oracle::swap_states(open), Some(HTLCStates::EXPIRED));
+		});
+	}
+
+	#[test]
+	fn should_scale_amounts_exactly() {
+		new_test_ext().execute_with(|| {
+			// the decimals the runtime bridges with, 8 of the ERC20 token against 15 of PRA
+			assert_eq!((PraTokenDecimals::get(), NativeDecimals::get()), (8, 15));
+			let token = U256::exp10(PraTokenDecimals::get() as usize);
+			let step = 10u128.pow((NativeDecimals::get() - PraTokenDecimals::get()) as u32);
+			assert_eq!(scaled(Oracle::to_balance(token)), Ok(DOLLARS));
+			assert_eq!(scaled(Oracle::to_token_amount(DOLLARS)), Ok(token));
+			assert_eq!(scaled(Oracle::to_balance(U256::from(1))), Ok(step));
+			assert_eq!(scaled(Oracle::to_token_amount(step)), Ok(U256::from(1)));
+
+			// PRA dust below the smallest token unit doesn't bridge
+			assert_eq!(scaled(Oracle::to_token_amount(step - 1)), Err(Error::<Runtime>::AmountPrecision.into()));
+			assert_eq!(scaled(Oracle::to_token_amount(DOLLARS + 1)), Err(Error::<Runtime>::AmountPrecision.into()));
+
+			// token amounts bridge up to the largest Balance
+			let max = U256::from(Balance::max_value() / step);
+			assert_eq!(scaled(Oracle::to_balance(max)), Ok(Balance::max_value() / step * step));
+			assert_eq!(scaled(Oracle::to_balance(max + 1)), Err(Error::<Runtime>::AmountOverflow.into()));
+			assert_eq!(scaled(Oracle::to_balance(U256::max_value())), Err(Error::<Runtime>::AmountOverflow.into()));
+
+			// a token with more decimals than PRA
+			assert_eq!(scaled(Oracle::scale(U256::exp10(18), 18, 15)), Ok(U256::exp10(15)));
+			assert_eq!(scaled(Oracle::scale(U256::from(999), 18, 15)), Err(Error::<Runtime>::AmountPrecision.into()));
+			assert_eq!(scaled(Oracle::scale(U256::from(1), 15, 18)), Ok(U256::from(1_000)));
+
+			// a token with fewer decimals than PRA
+			assert_eq!(scaled(Oracle::scale(U256::from(1), 6, 15)), Ok(U256::exp10(9)));
+			assert_eq!(scaled(Oracle::scale(U256::exp10(9) + 1, 15, 6)), Err(Error::<Runtime>::AmountPrecision.into()));
+			assert_eq!(scaled(Oracle::scale(U256::max_value(), 6, 15)), Err(Error::<Runtime>::AmountOverflow.into()));
+			assert_eq!(scaled(Oracle::scale(U256::from(1), 0, 78)), Err(Error::<Runtime>::AmountOverflow.into()));
+		});
+	}
+}
@@ -20,7 +20,7 @@ use crate::keyring::*;
 use keyring::{Ed25519Keyring, Sr25519Keyring};
 use node_runtime::{
 	GenesisConfig, BalancesConfig, SessionConfig, StakingConfig, SystemConfig,
-	GrandpaConfig, IndicesConfig, ContractsConfig, DidConfig, AdsConfig, WASM_BINARY,
+	GrandpaConfig, IndicesConfig, ContractsConfig, DidConfig, AdsConfig, OracleConfig, WASM_BINARY,
 };
 use hex_literal::hex;
 use node_runtime::constants::currency::*;
@@ -107,5 +107,9 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 			contract: hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into(),
 			min_deposit: 500 * DOLLARS,
 		}),
+		oracle: Some(OracleConfig {
+			authorities: vec![],
+			threshold: 2,
+		}),
 	}
 }